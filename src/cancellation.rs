@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Steps evaluated between two checks for cancellation.
+pub const CANCELLATION_CHECK_INTERVAL: u64 = 1_024;
+
+/// Lets another thread stop a running program. Clones share the same flag,
+/// so one clone can be kept by the interpreter and the other sent to the thread that cancels it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the running program, and every program evaluated until the token is reset.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,81 @@
+/// Groups of builtins that reach outside of the interpreter, and can be disabled
+/// for programs that shouldn't touch the host. Math, string, vector and map functions,
+/// flow control and definitions are always available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Reading from the stdin, running processes and exiting the program.
+    Io,
+    /// Reading and writing files.
+    Fs,
+    /// Environment variables and the arguments of the program.
+    Env,
+    /// The clock.
+    Time,
+    /// Random numbers.
+    Random,
+    /// Printing to the output of the interpreter.
+    Print,
+}
+
+/// The capabilities available to programs. Calling a builtin of a disabled
+/// capability is an unknown symbol, unless a variable with its name is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub io: bool,
+    pub fs: bool,
+    pub env: bool,
+    pub time: bool,
+    pub random: bool,
+    pub print: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Capabilities {
+    pub fn all() -> Self {
+        Self {
+            io: true,
+            fs: true,
+            env: true,
+            time: true,
+            random: true,
+            print: true,
+        }
+    }
+
+    /// Only the math, string, vector and map functions, for programs that can't be trusted.
+    pub fn pure() -> Self {
+        Self {
+            io: false,
+            fs: false,
+            env: false,
+            time: false,
+            random: false,
+            print: false,
+        }
+    }
+
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Io => self.io,
+            Capability::Fs => self.fs,
+            Capability::Env => self.env,
+            Capability::Time => self.time,
+            Capability::Random => self.random,
+            Capability::Print => self.print,
+        }
+    }
+}
+
+/// The capability a builtin belongs to, if any.
+pub fn builtin_capability(builtin: &str) -> Option<Capability> {
+    match builtin {
+        "print" => Some(Capability::Print),
+        "exit" => Some(Capability::Io),
+        _ => None,
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::LiwbError;
+use crate::limits::Limits;
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: liwb [options] [file | -e <expr> | -] [args...]
+
+Without a program to run, starts the REPL.
+The args after the program are given to it in the argv vector.
+
+Exits with the code given to (exit n), 1 for runtime errors and 2 for syntax errors.
+
+Options:
+  -e <expr>          evaluate the expression
+  -                  read the program from the stdin
+  -i                 start the REPL after running the program, with its definitions
+  --session <file>   restore the REPL session from the file, and save it there on exit
+  -h, --help         show this message";
+
+/// Exit code of a program that ran without errors.
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code of a program that failed while running.
+pub const EXIT_RUNTIME_ERROR: i32 = 1;
+/// Exit code of a program that could not be lexed or parsed.
+pub const EXIT_SYNTAX_ERROR: i32 = 2;
+/// Exit code for bad command line arguments.
+pub const EXIT_USAGE_ERROR: i32 = 64;
+/// Exit code for a program that could not be read.
+pub const EXIT_INPUT_ERROR: i32 = 66;
+
+/// Stack of the thread the liwb command runs on, much bigger than the 8 MiB of the main thread,
+/// so deep recursion works. Only the part that is used takes memory.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// The limits of the programs run by the liwb command, which are trusted:
+/// only the stack, leaving a quarter of it for the builtins and the REPL.
+pub fn limits() -> Limits {
+    Limits {
+        max_stack_size: Some(STACK_SIZE / 4 * 3),
+        ..Limits::unlimited()
+    }
+}
+
+/// Run f on a thread with a stack of STACK_SIZE, returning its result.
+/// A panic in f is resumed on the calling thread.
+pub fn with_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(f)
+        .expect("Failed to start the interpreter thread.")
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// The exit code of the process for a program that failed with the error.
+pub fn exit_code(err: &LiwbError) -> i32 {
+    match err {
+        LiwbError::Exit { code, .. } => *code,
+        err if err.is_syntax_error() => EXIT_SYNTAX_ERROR,
+        _ => EXIT_RUNTIME_ERROR,
+    }
+}
+
+/// Where the program to run comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    File(String),
+    Expression(String),
+    Stdin,
+}
+
+/// What the command line asked for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /// The program to run, or None to only start the REPL.
+    pub source: Option<Source>,
+    /// The arguments after the program.
+    pub argv: Vec<String>,
+    /// Start the REPL after running the program.
+    pub interactive: bool,
+    pub session: Option<PathBuf>,
+    pub help: bool,
+}
+
+/// Parse the arguments of the command line, without the name of the executable.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if options.source.is_some() {
+            options.argv.push(arg);
+            continue;
+        }
+        match arg.as_str() {
+            "-e" => {
+                let expression = args.next().ok_or("Expected an expression after -e.")?;
+                options.source = Some(Source::Expression(expression));
+            }
+            "-" => options.source = Some(Source::Stdin),
+            "-i" => options.interactive = true,
+            "--session" => {
+                let session = args.next().ok_or("Expected a file after --session.")?;
+                options.session = Some(PathBuf::from(session));
+            }
+            "-h" | "--help" => options.help = true,
+            option if option.starts_with('-') => {
+                return Err(format!("Unknown option: {option}.\n\n{USAGE}"))
+            }
+            file => options.source = Some(Source::File(file.to_string())),
+        }
+    }
+    Ok(options)
+}
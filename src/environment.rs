@@ -0,0 +1,281 @@
+use crate::literals::Literal;
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::rc::{Rc, Weak};
+
+pub type Variables = HashMap<String, Literal>;
+
+/// How many scopes are created before the first collection of cycles,
+/// and the least that are created between two collections.
+const MIN_COLLECTION_INTERVAL: usize = 1024;
+
+thread_local! {
+    static SCOPES: RefCell<Scopes> = const {
+        RefCell::new(Scopes {
+            scopes: Vec::new(),
+            threshold: MIN_COLLECTION_INTERVAL,
+        })
+    };
+}
+
+/// Every scope created on the thread, to find the ones kept alive only by cycles.
+struct Scopes {
+    scopes: Vec<Weak<RefCell<Scope>>>,
+    /// How many scopes can be registered before the next collection.
+    threshold: usize,
+}
+
+/// A scope of variables, linked to the scope where it was created.
+/// Cloning an environment is cheap, the clone points to the same scope.
+///
+/// A function holds the scope it was defined in, so a function stored in that scope,
+/// like a recursive function or a lambda bound by let, keeps the scope alive through a cycle.
+/// Those cycles are freed by collect_cycles, which runs as more scopes are created.
+#[derive(Clone)]
+pub struct Environment(Rc<RefCell<Scope>>);
+
+#[derive(Default)]
+struct Scope {
+    variables: Variables,
+    parent: Option<Environment>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::register(Scope::default())
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new empty scope that can see every variable of self.
+    pub fn child(&self) -> Self {
+        Self::register(Scope {
+            variables: HashMap::new(),
+            parent: Some(self.clone()),
+        })
+    }
+
+    fn register(scope: Scope) -> Self {
+        let env = Self(Rc::new(RefCell::new(scope)));
+        let collect = SCOPES.with_borrow_mut(|registry| {
+            registry.scopes.push(Rc::downgrade(&env.0));
+            if registry.scopes.len() <= registry.threshold {
+                return false;
+            }
+            // Most scopes are freed when their call returns, only collect if many are left.
+            registry.scopes.retain(|scope| scope.strong_count() > 0);
+            registry.scopes.len() > registry.threshold
+        });
+        if collect {
+            collect_cycles();
+        }
+        env
+    }
+
+    pub fn get(&self, name: &str) -> Option<Literal> {
+        let scope = self.0.borrow();
+        match scope.variables.get(name) {
+            Some(literal) => Some(literal.clone()),
+            None => scope.parent.as_ref()?.get(name),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        let scope = self.0.borrow();
+        scope.variables.contains_key(name)
+            || scope
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.contains(name))
+    }
+
+    /// The name of every variable this scope can see, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let scope = self.0.borrow();
+        let mut names = match &scope.parent {
+            Some(parent) => parent.names(),
+            None => Vec::new(),
+        };
+        names.extend(scope.variables.keys().cloned());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Bind the name on this scope, shadowing any variable with the same name on the parents.
+    pub fn define(&self, name: impl Into<String>, literal: Literal) {
+        self.0.borrow_mut().variables.insert(name.into(), literal);
+    }
+
+    /// Rebind the name on the closest scope where it is defined,
+    /// or define it on this scope if it does not exist yet.
+    pub fn assign(&self, name: impl Into<String>, literal: Literal) {
+        let name = name.into();
+        let mut env = self.clone();
+        loop {
+            if env.0.borrow().variables.contains_key(&name) {
+                env.define(name, literal);
+                return;
+            }
+            let parent = env.0.borrow().parent.clone();
+            match parent {
+                Some(parent) => env = parent,
+                None => break,
+            }
+        }
+        self.define(name, literal);
+    }
+}
+
+/// Free the scopes of the thread that are only kept alive by cycles between them.
+///
+/// A scope is kept when something besides other scopes holds it, like an interpreter,
+/// a literal held by Rust code or a scope being evaluated, and so is every scope it can reach.
+/// The rest are unreachable and get emptied, which breaks their cycles.
+pub fn collect_cycles() {
+    // The registry is gone when the thread is exiting.
+    let Ok(scopes) = SCOPES.try_with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.scopes.retain(|scope| scope.strong_count() > 0);
+        registry
+            .scopes
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>()
+    }) else {
+        return;
+    };
+    let index = scopes
+        .iter()
+        .enumerate()
+        .map(|(i, scope)| (Rc::as_ptr(scope), i))
+        .collect::<HashMap<_, _>>();
+
+    // References from outside of the scopes: the strong count, minus the one taken above
+    // and the ones from other scopes. Scopes being modified can't be read, so they are kept.
+    let mut outside = scopes
+        .iter()
+        .map(|scope| Rc::strong_count(scope) - 1)
+        .collect::<Vec<_>>();
+    let mut edges = Vec::with_capacity(scopes.len());
+    // How many literals each scope holds, to know how long collecting the live ones takes.
+    let mut sizes = vec![1; scopes.len()];
+    for (i, scope) in scopes.iter().enumerate() {
+        let Ok(scope) = scope.try_borrow() else {
+            edges.push(None);
+            continue;
+        };
+        let mut targets = Vec::new();
+        let mut visit = |env: &Environment| {
+            if let Some(&target) = index.get(&Rc::as_ptr(&env.0)) {
+                outside[target] -= 1;
+                targets.push(target);
+            }
+        };
+        if let Some(parent) = &scope.parent {
+            visit(parent);
+        }
+        for literal in scope.variables.values() {
+            sizes[i] += environments(literal, &mut visit);
+        }
+        edges.push(Some(targets));
+    }
+
+    let mut reachable = vec![false; scopes.len()];
+    let mut pending = (0..scopes.len())
+        .filter(|&i| outside[i] > 0 || edges[i].is_none())
+        .collect::<Vec<_>>();
+    while let Some(i) = pending.pop() {
+        if std::mem::replace(&mut reachable[i], true) {
+            continue;
+        }
+        pending.extend(
+            edges[i]
+                .iter()
+                .flatten()
+                .filter(|&&target| !reachable[target]),
+        );
+    }
+
+    // Empty every unreachable scope before dropping their contents,
+    // so freeing a long chain of scopes doesn't recurse through it.
+    let garbage = scopes
+        .iter()
+        .zip(&reachable)
+        .filter(|(_, reachable)| !**reachable)
+        .map(|(scope, _)| std::mem::take(&mut *scope.borrow_mut()))
+        .collect::<Vec<_>>();
+    drop(garbage);
+    drop(scopes);
+    // Wait for as many new scopes as the work the next collection will take at least,
+    // so collecting takes a constant time for each scope created.
+    let live = reachable.iter().filter(|reachable| **reachable).count();
+    let work = (0..sizes.len())
+        .filter(|&i| reachable[i])
+        .map(|i| sizes[i])
+        .sum::<usize>();
+    let _ = SCOPES.try_with(|registry| {
+        registry.borrow_mut().threshold = live + work.max(MIN_COLLECTION_INTERVAL);
+    });
+}
+
+/// How many scopes are alive on the thread, including the ones only kept alive by cycles.
+pub fn live_scopes() -> usize {
+    SCOPES.with_borrow(|registry| {
+        registry
+            .scopes
+            .iter()
+            .filter(|scope| scope.strong_count() > 0)
+            .count()
+    })
+}
+
+/// Call visit with every environment held by the literal, returning how many literals were seen.
+fn environments(literal: &Literal, visit: &mut impl FnMut(&Environment)) -> usize {
+    match literal {
+        Literal::Function { body, env, .. } => {
+            visit(env);
+            1 + environments(body, visit)
+        }
+        Literal::List(literals, _) | Literal::Vector(literals) => {
+            1 + literals
+                .iter()
+                .map(|literal| environments(literal, visit))
+                .sum::<usize>()
+        }
+        Literal::Map(map) => {
+            1 + map
+                .values()
+                .map(|literal| environments(literal, visit))
+                .sum::<usize>()
+        }
+        _ => 1,
+    }
+}
+
+// Functions hold the environment they were defined in, and a recursive function is part of
+// its own environment, so environments are compared and printed by identity only.
+impl Debug for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Environment({:p})", Rc::as_ptr(&self.0))
+    }
+}
+
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for Environment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
@@ -0,0 +1,279 @@
+use crate::limits::Limit;
+use crate::literals::Literal;
+use crate::span::Span;
+
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LiwbError {
+    /// The source could not be split into tokens.
+    Lex {
+        message: String,
+        span: Span,
+    },
+    /// The tokens do not form a valid program.
+    Parse {
+        message: String,
+        span: Span,
+    },
+    UnknownSymbol {
+        name: String,
+        span: Span,
+    },
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+        span: Span,
+    },
+    /// Wrong number of arguments. For operations taking any number of arguments,
+    /// expected is the minimum.
+    Arity {
+        name: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    /// A vector with less elements than the names it is destructured into.
+    PatternMismatch {
+        names: usize,
+        elements: usize,
+        span: Span,
+    },
+    IndexOutOfBounds {
+        index: i64,
+        length: usize,
+        span: Span,
+    },
+    DeletedLiteral {
+        literal: String,
+        span: Span,
+    },
+    Io {
+        message: String,
+        span: Span,
+    },
+    /// Raised by the script itself with the error function.
+    Raised {
+        message: String,
+        span: Span,
+    },
+    /// The program used more resources than the interpreter limits allow.
+    LimitExceeded {
+        limit: Limit,
+        span: Span,
+    },
+    /// The program was stopped by a cancellation token or a timeout.
+    Cancelled {
+        span: Span,
+    },
+    /// The program asked to stop with the exit function, with the given exit code.
+    Exit {
+        code: i32,
+        span: Span,
+    },
+}
+
+impl LiwbError {
+    pub fn lex(message: impl Into<String>, span: Span) -> Self {
+        Self::Lex {
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn parse(message: impl Into<String>, span: Span) -> Self {
+        Self::Parse {
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn unknown_symbol(name: impl Into<String>) -> Self {
+        Self::UnknownSymbol {
+            name: name.into(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn type_mismatch(expected: &'static str, found: &Literal) -> Self {
+        Self::TypeMismatch {
+            expected,
+            found: found.type_name(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn arity(name: impl Into<String>, expected: usize, found: usize) -> Self {
+        Self::Arity {
+            name: name.into(),
+            expected,
+            found,
+            span: Span::default(),
+        }
+    }
+
+    pub fn pattern_mismatch(names: usize, elements: usize) -> Self {
+        Self::PatternMismatch {
+            names,
+            elements,
+            span: Span::default(),
+        }
+    }
+
+    pub fn index_out_of_bounds(index: i64, length: usize) -> Self {
+        Self::IndexOutOfBounds {
+            index,
+            length,
+            span: Span::default(),
+        }
+    }
+
+    pub fn deleted(literal: &Literal) -> Self {
+        Self::DeletedLiteral {
+            literal: literal.to_string(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::Io {
+            message: message.into(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn raised(message: impl Into<String>) -> Self {
+        Self::Raised {
+            message: message.into(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn limit_exceeded(limit: Limit) -> Self {
+        Self::LimitExceeded {
+            limit,
+            span: Span::default(),
+        }
+    }
+
+    pub fn cancelled() -> Self {
+        Self::Cancelled {
+            span: Span::default(),
+        }
+    }
+
+    pub fn exit(code: i32) -> Self {
+        Self::Exit {
+            code,
+            span: Span::default(),
+        }
+    }
+
+    /// Whether the source could not be read as liwb code, as opposed to failing while running.
+    pub fn is_syntax_error(&self) -> bool {
+        matches!(self, Self::Lex { .. } | Self::Parse { .. })
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Lex { span, .. }
+            | Self::Parse { span, .. }
+            | Self::UnknownSymbol { span, .. }
+            | Self::TypeMismatch { span, .. }
+            | Self::Arity { span, .. }
+            | Self::PatternMismatch { span, .. }
+            | Self::IndexOutOfBounds { span, .. }
+            | Self::DeletedLiteral { span, .. }
+            | Self::Io { span, .. }
+            | Self::Raised { span, .. }
+            | Self::LimitExceeded { span, .. }
+            | Self::Cancelled { span }
+            | Self::Exit { span, .. } => *span,
+        }
+    }
+
+    fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Self::Lex { span, .. }
+            | Self::Parse { span, .. }
+            | Self::UnknownSymbol { span, .. }
+            | Self::TypeMismatch { span, .. }
+            | Self::Arity { span, .. }
+            | Self::PatternMismatch { span, .. }
+            | Self::IndexOutOfBounds { span, .. }
+            | Self::DeletedLiteral { span, .. }
+            | Self::Io { span, .. }
+            | Self::Raised { span, .. }
+            | Self::LimitExceeded { span, .. }
+            | Self::Cancelled { span }
+            | Self::Exit { span, .. } => span,
+        }
+    }
+
+    /// Attach a location to the error, unless a more precise one is already known.
+    pub fn at(mut self, span: Span) -> Self {
+        let current = self.span_mut();
+        if !current.is_known() {
+            *current = span;
+        }
+        self
+    }
+
+    /// The error description, without its location.
+    pub fn message(&self) -> String {
+        match self {
+            Self::Lex { message, .. }
+            | Self::Parse { message, .. }
+            | Self::Io { message, .. }
+            | Self::Raised { message, .. } => message.clone(),
+            Self::UnknownSymbol { name, .. } => format!("Unknow symbol: {name}"),
+            Self::TypeMismatch {
+                expected, found, ..
+            } => format!("Expected {expected}, found {found}."),
+            Self::Arity {
+                name,
+                expected,
+                found,
+                ..
+            } => {
+                format!("Wrong number of arguments for {name}. Expected {expected}, found {found}.")
+            }
+            Self::PatternMismatch {
+                names, elements, ..
+            } => {
+                format!("Can not destructure a vector of {elements} elements into {names} names.")
+            }
+            Self::IndexOutOfBounds { index, length, .. } => {
+                format!("Index {index} is out of bounds for a vector of length {length}.")
+            }
+            Self::DeletedLiteral { literal, .. } => {
+                format!("Trying to evaluate a deleted literal: {literal}")
+            }
+            Self::LimitExceeded { limit, .. } => format!("Exceeded {limit}."),
+            Self::Cancelled { .. } => "The evaluation was cancelled.".to_string(),
+            Self::Exit { code, .. } => format!("The program exited with code {code}."),
+        }
+    }
+
+    pub fn render(&self, file: &str, source: &str) -> String {
+        self.span().render(file, source, &self.message())
+    }
+}
+
+impl From<std::io::Error> for LiwbError {
+    fn from(err: std::io::Error) -> Self {
+        Self::io(err.to_string())
+    }
+}
+
+impl Display for LiwbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        if span.is_known() {
+            return write!(f, "{}:{}: {}", span.line, span.column, self.message());
+        }
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for LiwbError {}
@@ -1,172 +1,337 @@
+use crate::cancellation::{CancellationToken, CANCELLATION_CHECK_INTERVAL};
+use crate::capabilities::{builtin_capability, Capabilities};
+use crate::environment::Environment;
+use crate::error::LiwbError;
+use crate::flow_control::*;
 use crate::functions::*;
+use crate::interpreter::Interpreter;
+use crate::limits::{Limit, Limits};
 use crate::literals::*;
+use crate::map_manipulation::*;
 use crate::math_functions::*;
+use crate::span::Span;
 use crate::vector_manipulation::*;
 
-use std::collections::HashMap;
+use std::io::Write;
+use std::time::Instant;
 
-pub type Variables = HashMap<String, Literal>;
-
-const SINGLE_ARG_MATH_OPERATORS: [&'static str; 9] = [
+const SINGLE_ARG_MATH_OPERATORS: [&str; 9] = [
     "sqrt", "sin", "cos", "tan", "abs", "log10", "floor", "ceil", "round",
 ];
 
-const DOUBLE_ARG_MATH_OPERATORS: [&'static str; 1] = ["mod"];
+const DOUBLE_ARG_MATH_OPERATORS: [&str; 1] = ["mod"];
+
+const VECTOR_OPERATORS: [&str; 5] = ["nth", "join", "range", "map", "filter"];
+
+const MAP_OPERATORS: [&str; 8] = [
+    "hash-map", "get", "assoc", "dissoc", "keys", "values", "has?", "merge",
+];
+
+/// Builtins that are not part of a group of operators.
+const FORMS: [&str; 20] = [
+    "if", "true", "false", "fn", "lambda", "define", "print", "str", "delete", "error", "exit",
+    "do", "let", "cond", "when", "unless", "else", "and", "or", "not",
+];
+
+/// The name of every builtin, like the REPL completes them.
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    FORMS
+        .into_iter()
+        .chain(SINGLE_ARG_MATH_OPERATORS)
+        .chain(DOUBLE_ARG_MATH_OPERATORS)
+        .chain(VECTOR_OPERATORS)
+        .chain(MAP_OPERATORS)
+}
+
+pub fn eval_from_literals(literals: Vec<Literal>) -> Result<Vec<Literal>, LiwbError> {
+    Interpreter::new().eval_literals(literals)
+}
+
+/// Result of evaluating a list. Lists whose value is the value of another literal,
+/// like the branches of an if or the body of a function, hand that literal back
+/// instead of evaluating it, so eval_literal can continue with it without growing the stack.
+pub enum Step {
+    Done(Literal),
+    Tail(Literal, Environment),
+}
+
+/// Everything a program can change besides its variables.
+pub struct State {
+    pub deleted: Vec<Literal>,
+    /// Where print writes to.
+    pub output: Box<dyn Write>,
+    pub limits: Limits,
+    /// Literals evaluated since the interpreter was last called.
+    pub steps: u64,
+    /// How many evaluations are currently nested.
+    pub depth: usize,
+    /// Address of the stack where the outermost evaluation started.
+    pub stack_start: usize,
+    pub cancellation: CancellationToken,
+    /// When the current evaluation times out.
+    pub deadline: Option<Instant>,
+    pub capabilities: Capabilities,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            deleted: Vec::new(),
+            output: Box::new(std::io::stdout()),
+            limits: Limits::default(),
+            steps: 0,
+            depth: 0,
+            stack_start: 0,
+            cancellation: CancellationToken::new(),
+            deadline: None,
+            capabilities: Capabilities::default(),
+        }
+    }
+}
+
+impl State {
+    fn step(&mut self) -> Result<(), LiwbError> {
+        self.steps += 1;
+        if self.steps.is_multiple_of(CANCELLATION_CHECK_INTERVAL) {
+            self.check_cancelled()?;
+        }
+        match self.limits.max_steps {
+            Some(max_steps) if self.steps > max_steps => {
+                Err(LiwbError::limit_exceeded(Limit::Steps(max_steps)))
+            }
+            _ => Ok(()),
+        }
+    }
 
-const VECTOR_OPERATORS: [&'static str; 5] = ["nth", "join", "range", "map", "filter"];
+    pub fn check_cancelled(&self) -> Result<(), LiwbError> {
+        let timed_out = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+        if timed_out || self.cancellation.is_cancelled() {
+            return Err(LiwbError::cancelled());
+        }
+        Ok(())
+    }
 
-pub fn eval_from_literals(literals: Vec<Literal>) -> Result<Vec<Literal>, String> {
-    let mut variables: Variables = HashMap::new();
-    let mut deleted_literals: Vec<Literal> = Vec::new();
-    let mut results: Vec<Literal> = Vec::new();
-    for literal in literals.into_iter() {
-        results.push(eval_literal(
-            literal,
-            &mut variables,
-            &mut deleted_literals,
-        )?);
+    /// Check the size of a vector, map or string before it is built.
+    pub fn check_size(&self, size: usize) -> Result<(), LiwbError> {
+        match self.limits.max_collection_size {
+            Some(max_size) if size > max_size => {
+                Err(LiwbError::limit_exceeded(Limit::CollectionSize(max_size)))
+            }
+            _ => Ok(()),
+        }
     }
-    Ok(results)
 }
 
 pub fn eval_literal(
     literal: Literal,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
-    if deleted.contains(&literal) {
-        return Err(format!(
-            "Trying to evaluate a deleted literal: {:?}",
-            literal
-        ));
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    if let Some(max_depth) = state.limits.max_depth {
+        if state.depth >= max_depth {
+            return Err(LiwbError::limit_exceeded(Limit::Depth(max_depth)));
+        }
     }
-    match literal {
-        Literal::Void
-        | Literal::Number(_)
-        | Literal::String(_)
-        | Literal::Vector(_)
-        | Literal::Boolean(_) => Ok(literal),
-        Literal::List(list) => eval_list(list, variables, deleted),
-        Literal::Symbol(s) => {
-            let Some(literal) = variables.get(&s) else {
-                return Err(format!("Unknow symbol {s}"));
-            };
-            Ok(literal.clone())
+    let marker = 0u8;
+    let stack_position = std::ptr::addr_of!(marker) as usize;
+    if state.depth == 0 {
+        state.stack_start = stack_position;
+    }
+    if let Some(max_stack_size) = state.limits.max_stack_size {
+        if stack_position.abs_diff(state.stack_start) > max_stack_size {
+            return Err(LiwbError::limit_exceeded(Limit::StackSize(max_stack_size)));
         }
-        _ => todo!("Missing literal implementation for {:?}", literal),
     }
+    state.depth += 1;
+    let result = eval_nested_literal(literal, env, state);
+    state.depth -= 1;
+    result
 }
 
-fn eval_list(
-    list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+fn eval_nested_literal(
+    literal: Literal,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let mut literal = literal;
+    let mut env = env.clone();
+    loop {
+        state.step()?;
+        if state.deleted.contains(&literal) {
+            return Err(LiwbError::deleted(&literal));
+        }
+        match literal {
+            Literal::Void
+            | Literal::Number(_)
+            | Literal::String(_)
+            | Literal::Vector(_)
+            | Literal::Map(_)
+            | Literal::Boolean(_)
+            | Literal::Function { .. }
+            | Literal::NativeFunction(_) => return Ok(literal),
+            Literal::List(list, span) => {
+                match eval_list(list, &env, state).map_err(|err| err.at(span))? {
+                    Step::Done(literal) => return Ok(literal),
+                    Step::Tail(tail, tail_env) => {
+                        literal = tail;
+                        env = tail_env;
+                    }
+                }
+            }
+            Literal::Symbol(s) => {
+                let Some(literal) = env.get(&s) else {
+                    return Err(LiwbError::unknown_symbol(s));
+                };
+                return Ok(literal);
+            }
+            // Operators and if only mean something at the head of a list.
+            Literal::MathOperator(_) | Literal::BinaryOperator(_) | Literal::If => {
+                return Err(LiwbError::type_mismatch("value", &literal))
+            }
+        }
+    }
+}
+
+fn eval_list(list: Vec<Literal>, env: &Environment, state: &mut State) -> Result<Step, LiwbError> {
     let head = list[0].clone();
-    if deleted.contains(&head) {
-        return Err(format!("Trying to evaluate a deleted literal: {:?}", head));
+    if state.deleted.contains(&head) {
+        return Err(LiwbError::deleted(&head));
     }
 
     match head {
-        Literal::List(list) => eval_list(list.to_vec(), variables, deleted),
-        Literal::Void => Ok(Literal::Void),
-        Literal::MathOperator(_) => eval_math_operator(list, variables, deleted),
-        Literal::BinaryOperator(_) => eval_binary_operator(list, variables, deleted),
-        Literal::If => eval_if(list, variables, deleted),
-        Literal::Vector(_) => eval_literal(head, variables, deleted),
-        Literal::Symbol(s) => match s.as_str() {
-            "fn" => define_function(list, variables),
-            "define" => define_variable(list, variables, deleted),
-            "print" => eval_print(list, variables, deleted),
-            "do" => eval_do(list, variables, deleted),
-            "str" => eval_str(list, variables, deleted),
-            "delete" => eval_delete(list, deleted),
-            s if SINGLE_ARG_MATH_OPERATORS.contains(&s) => {
-                eval_operator_with_single_arg(list, variables, deleted)
-            }
-            s if DOUBLE_ARG_MATH_OPERATORS.contains(&s) => {
-                eval_operator_with_double_argument(list, variables, deleted)
-            }
-            s if VECTOR_OPERATORS.contains(&s) => eval_vector_operation(list, variables, deleted),
-            _ => {
-                if let Some(literal) = variables.get(&s) {
-                    if let Literal::Function { .. } = literal {
-                        return eval_function(literal.clone(), list, variables, deleted);
-                    }
-                    Ok(literal.clone())
-                } else {
-                    return Err(format!("Unknow symbol: {s}"));
-                }
+        Literal::List(..) => {
+            let head = eval_literal(head, env, state)?;
+            if let Literal::Function { .. } | Literal::NativeFunction(_) = head {
+                return call_function(head, list, env, state);
             }
+            Ok(Step::Done(head))
+        }
+        Literal::Void => Ok(Step::Done(Literal::Void)),
+        Literal::MathOperator(_) => eval_math_operator(list, env, state).map(Step::Done),
+        Literal::BinaryOperator(_) => eval_binary_operator(list, env, state).map(Step::Done),
+        Literal::If => eval_if(list, env, state),
+        Literal::Vector(_) | Literal::Map(_) => eval_literal(head, env, state).map(Step::Done),
+        Literal::Symbol(s) => match s.as_str() {
+            "do" => eval_do(list, env, state),
+            "let" => eval_let(list, env, state),
+            "cond" => eval_cond(list, env, state),
+            "when" | "unless" => eval_when(list, env, state),
+            "and" | "or" => eval_logical_operator(list, env, state).map(Step::Done),
+            "not" => eval_not(list, env, state).map(Step::Done),
+            s => eval_symbol_list(s, list, env, state),
         },
-        Literal::String(_) 
-        | Literal::Boolean(_) 
-        | Literal::Number(_) => Ok(Literal::List(list)),
-        Literal::Function { .. } => Ok(head),
+        Literal::String(_) | Literal::Boolean(_) | Literal::Number(_) if list.len() == 1 => {
+            Ok(Step::Done(head))
+        }
+        Literal::String(_) | Literal::Boolean(_) | Literal::Number(_) => {
+            Ok(Step::Done(Literal::List(list, Span::default())))
+        }
+        Literal::Function { .. } | Literal::NativeFunction(_) => Ok(Step::Done(head)),
+    }
+}
+
+fn eval_symbol_list(
+    symbol: &str,
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
+    let disabled =
+        builtin_capability(symbol).is_some_and(|capability| !state.capabilities.allows(capability));
+    match symbol {
+        s if disabled => return eval_variable_call(s, list, env, state),
+        "fn" => define_function(list, env),
+        "lambda" => define_lambda(list, env),
+        "define" => define_variable(list, env, state),
+        "print" => eval_print(list, env, state),
+        "str" => eval_str(list, env, state),
+        "delete" => eval_delete(list, state),
+        "error" => eval_error(list, env, state),
+        "exit" => eval_exit(list, env, state),
+        s if SINGLE_ARG_MATH_OPERATORS.contains(&s) => {
+            eval_operator_with_single_arg(list, env, state)
+        }
+        s if DOUBLE_ARG_MATH_OPERATORS.contains(&s) => {
+            eval_operator_with_double_argument(list, env, state)
+        }
+        s if VECTOR_OPERATORS.contains(&s) => eval_vector_operation(list, env, state),
+        s if MAP_OPERATORS.contains(&s) => eval_map_operation(list, env, state),
+        s => return eval_variable_call(s, list, env, state),
+    }
+    .map(Step::Done)
+}
+
+/// Call the function stored in the variable, or return the value of the variable.
+fn eval_variable_call(
+    symbol: &str,
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
+    let Some(literal) = env.get(symbol) else {
+        return Err(LiwbError::unknown_symbol(symbol));
+    };
+    if let Literal::Function { .. } | Literal::NativeFunction(_) = literal {
+        return call_function(literal, list, env, state);
     }
+    Ok(Step::Done(literal))
 }
 
 fn eval_math_operator(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     let mut list = list.into_iter();
     let operator = list.next();
 
     let Some(Literal::MathOperator(operator)) = operator else {
-        return Err(format!(
-            "Error. Expected Literal::MathOperator, found: {:?}",
-            operator
+        return Err(LiwbError::type_mismatch(
+            "operator",
+            &operator.unwrap_or(Literal::Void),
         ));
     };
 
     let head = list
         .next()
-        .ok_or(format!("Error. Could not get the head of the operation."))?;
+        .ok_or_else(|| LiwbError::arity(operator.to_string(), 1, 0))?;
 
-    let Literal::Number(head) = eval_literal(head.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error. Expected head to be Literal::Number, found: {:?}",
-            head
-        ));
+    let head = eval_literal(head, env, state)?;
+    let Literal::Number(head) = head else {
+        return Err(LiwbError::type_mismatch("number", &head));
     };
 
     list.try_fold(Literal::Number(head), |acc, literal| {
-        let literal = eval_literal(literal, variables, deleted)?;
+        let literal = eval_literal(literal, env, state)?;
         let Literal::Number(n) = literal else {
-            return Err(format!(
-                "Error. Expected Literal::Number for the literal, found {:?}",
-                literal
-            ));
+            return Err(LiwbError::type_mismatch("number", &literal));
         };
-        let acc = eval_literal(acc, variables, deleted)?;
+        let acc = eval_literal(acc, env, state)?;
         let Literal::Number(acc) = acc else {
-            return Err(format!(
-                "Error. Expected Literal::Number for the acumulator, found {:?}",
-                acc
-            ));
+            return Err(LiwbError::type_mismatch("number", &acc));
         };
-        return Ok(Literal::Number(match operator {
+        Ok(Literal::Number(match operator {
             MathOperators::Add => acc + n,
             MathOperators::Subtract => acc - n,
             MathOperators::Multiply => acc * n,
             MathOperators::Divide => acc / n,
-        }));
+        }))
     })
 }
 
 fn eval_binary_operator(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     let mut list = list.into_iter();
     let operator = list.next();
     let Some(Literal::BinaryOperator(operator)) = operator else {
-        return Err(format!(
-            "Error. Expected Literal::BinaryOperator, found: {:?}",
-            operator
+        return Err(LiwbError::type_mismatch(
+            "operator",
+            &operator.unwrap_or(Literal::Void),
         ));
     };
     let list = vec![Literal::BinaryOperator(operator.clone())]
@@ -180,101 +345,87 @@ fn eval_binary_operator(
         | Operator::BiggerThan
         | Operator::LessOrEqualThan
         | Operator::BiggerOrEqualThan
-        | Operator::NotEqual => eval_relation_operator(list, variables, deleted),
+        | Operator::NotEqual => eval_relation_operator(list, env, state),
     }
 }
 
-fn eval_if(
-    list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+fn eval_if(list: Vec<Literal>, env: &Environment, state: &mut State) -> Result<Step, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter().skip(1);
 
     let statement = list
         .next()
-        .ok_or(format!("Missing boolean statement for IF"))?;
+        .ok_or_else(|| LiwbError::arity("if", 3, found))?;
 
-    let left = list.next().ok_or(format!("Missing left side for IF"))?;
+    let left = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("if", 3, found))?;
 
-    let right = list.next().ok_or(format!("Missing  right side of IF"))?;
+    let right = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("if", 3, found))?;
 
-    let Literal::Boolean(statement) = eval_literal(statement.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error: expected Literal::Boolean. Found {:?}",
-            statement
-        ));
+    let statement = eval_literal(statement, env, state)?;
+    let Literal::Boolean(statement) = statement else {
+        return Err(LiwbError::type_mismatch("boolean", &statement));
     };
 
     if statement {
-        return eval_literal(left.clone(), variables, deleted);
+        return Ok(Step::Tail(left, env.clone()));
     }
-    return eval_literal(right.clone(), variables, deleted);
+    Ok(Step::Tail(right, env.clone()))
 }
 
 fn define_variable(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter().skip(1);
 
     let name = list
         .next()
-        .ok_or(format!("Error. Missing variable name for definition"))?;
+        .ok_or_else(|| LiwbError::arity("define", 2, found))?;
 
     let Literal::Symbol(name) = name else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol for variable name, found: {:?}",
-            name
-        ));
+        return Err(LiwbError::type_mismatch("symbol", &name));
     };
 
-    let literal = list.next().ok_or(format!(
-        "Error. Missing literal value for variable definition."
-    ))?;
+    let literal = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("define", 2, found))?;
 
-    let literal = eval_literal(literal.clone(), variables, deleted)?;
+    let literal = eval_literal(literal.clone(), env, state)?;
 
-    variables.insert(name.to_string(), literal);
+    env.assign(name, literal);
 
     Ok(Literal::Void)
 }
 
 fn eval_relation_operator(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     let list_size = list.len();
-    if list_size != 3 {
-        return Err(format!(
-            "Error. Wrong number of arguments for relational operation. Expected 3, found: {list_size}"
-        ));
-    }
-
     let mut list = list.into_iter();
 
-    let operator = list.next().ok_or(format!(
-        "Error. Missing operator for the relational operation"
-    ))?;
+    let operator = list.next().unwrap_or(Literal::Void);
 
     let Literal::BinaryOperator(operator) = operator else {
-        return Err(format!(
-            "Error. Expected Literal::BinaryOperator for the operator, found: {:?} ",
-            list
-        ));
+        return Err(LiwbError::type_mismatch("operator", &operator));
     };
 
-    let left = list.next().ok_or(format!(
-        "Error. Missing left value for the relational operation"
-    ))?;
-    let left = eval_literal(left, variables, deleted)?;
+    if list_size != 3 {
+        return Err(LiwbError::arity(operator.to_string(), 2, list_size - 1));
+    }
 
-    let right = list
-        .next()
-        .ok_or(format!("Error. Missing left value for operation"))?;
-    let right = eval_literal(right, variables, deleted)?;
+    let left = list.next().unwrap_or(Literal::Void);
+    let left = eval_literal(left, env, state)?;
+
+    let right = list.next().unwrap_or(Literal::Void);
+    let right = eval_literal(right, env, state)?;
 
     Ok(Literal::Boolean(match operator {
         Operator::Equal => left == right,
@@ -288,85 +439,156 @@ fn eval_relation_operator(
 
 fn eval_print(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
-    list.into_iter()
-        .skip(1)
-        .map(|literal| eval_literal(literal, variables, deleted))
-        .collect::<Result<Vec<_>, String>>()?
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let literals = list
         .into_iter()
-        .for_each(|literal| {
-            let mut literal = literal;
-            if let Literal::String(ref s) = literal {
-                let regex = regex::Regex::new(r"\\(.)").unwrap();
-                let string = regex
-                    .replace_all(s, |capture: &regex::Captures| {
-                        match &capture[1] {
-                            "n" => "\n",
-                            "t" => "\t",
-                            "r" => "\r",
-                            _ => &capture[1],
-                        }
-                        .to_string()
-                    })
-                    .to_string();
-
-                literal = Literal::String(string);
-            }
-            print!("{literal}\n")
-        });
+        .skip(1)
+        .map(|literal| eval_literal(literal, env, state))
+        .collect::<Result<Vec<_>, LiwbError>>()?;
+    for literal in literals {
+        writeln!(state.output, "{literal}")?;
+    }
     Ok(Literal::Void)
 }
 
-fn eval_do(
-    list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
-    list.into_iter()
-        .skip(1)
-        .map(|literal| eval_literal(literal, variables, deleted))
-        .collect::<Result<Vec<_>, String>>()?;
-    Ok(Literal::Void)
+fn eval_do(list: Vec<Literal>, env: &Environment, state: &mut State) -> Result<Step, LiwbError> {
+    eval_body(list.into_iter().skip(1).collect(), env, state)
+}
+
+/// Evaluate every literal but the last, which is left for eval_literal.
+pub fn eval_body(
+    mut body: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
+    let Some(last) = body.pop() else {
+        return Ok(Step::Done(Literal::Void));
+    };
+    for literal in body {
+        eval_literal(literal, env, state)?;
+    }
+    Ok(Step::Tail(last, env.clone()))
+}
+
+fn eval_let(list: Vec<Literal>, env: &Environment, state: &mut State) -> Result<Step, LiwbError> {
+    let found = list.len() - 1;
+    let mut list = list.into_iter().skip(1);
+
+    let bindings = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("let", 1, found))?;
+    let bindings = match bindings {
+        Literal::Vector(bindings) => bindings,
+        Literal::Void => vec![],
+        bindings => return Err(LiwbError::type_mismatch("vector", &bindings)),
+    };
+    if !bindings.len().is_multiple_of(2) {
+        return Err(LiwbError::arity(
+            "let bindings",
+            bindings.len() + 1,
+            bindings.len(),
+        ));
+    }
+
+    let scope = env.child();
+    let mut bindings = bindings.into_iter();
+    while let (Some(pattern), Some(value)) = (bindings.next(), bindings.next()) {
+        let value = eval_literal(value, &scope, state)?;
+        bind_pattern(pattern, value, &scope, state)?;
+    }
+
+    eval_body(list.collect(), &scope, state)
+}
+
+/// Bind a symbol to the value, or each symbol of a vector pattern to the matching element.
+fn bind_pattern(
+    pattern: Literal,
+    value: Literal,
+    env: &Environment,
+    state: &mut State,
+) -> Result<(), LiwbError> {
+    match pattern {
+        Literal::Symbol(name) => env.define(name, value),
+        Literal::Vector(patterns) => {
+            let Literal::Vector(elements) = value else {
+                return Err(LiwbError::type_mismatch("vector", &value));
+            };
+            if elements.len() < patterns.len() {
+                return Err(LiwbError::pattern_mismatch(patterns.len(), elements.len()));
+            }
+            for (pattern, element) in patterns.into_iter().zip(elements) {
+                let element = eval_element(element, env, state)?;
+                bind_pattern(pattern, element, env, state)?;
+            }
+        }
+        pattern => return Err(LiwbError::type_mismatch("symbol", &pattern)),
+    }
+    Ok(())
 }
 
 fn eval_str(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
-    Ok(list
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let string = list
         .into_iter()
         .skip(1)
-        .map(|literal| eval_literal(literal, variables, deleted))
-        .collect::<Result<Vec<_>, String>>()?
+        .map(|literal| eval_literal(literal, env, state))
+        .collect::<Result<Vec<_>, LiwbError>>()?
         .into_iter()
         .reduce(|acc, literal| {
-            let mut literal = literal;
-            if let Literal::String(ref s) = literal {
-                let regex = regex::Regex::new(r"\\(.)").unwrap();
-                let string = regex
-                    .replace_all(s, |capture: &regex::Captures| {
-                        match &capture[1] {
-                            "n" => "\n",
-                            "t" => "\t",
-                            "r" => "\r",
-                            _ => &capture[1],
-                        }
-                        .to_string()
-                    })
-                    .to_string();
-                literal = Literal::String(string);
-            }
             let acc = acc.to_string();
             let literal = literal.to_string();
             Literal::String(format!("{acc}{literal}"))
         })
-        .unwrap_or(Literal::Void))
+        .unwrap_or(Literal::Void);
+    if let Literal::String(s) = &string {
+        state.check_size(s.chars().count())?;
+    }
+    Ok(string)
 }
 
-fn eval_delete(list: Vec<Literal>, deleted: &mut Vec<Literal>) -> Result<Literal, String> {
-    list.into_iter().for_each(|literal| deleted.push(literal));
+fn eval_delete(list: Vec<Literal>, state: &mut State) -> Result<Literal, LiwbError> {
+    list.into_iter()
+        .for_each(|literal| state.deleted.push(literal));
     Ok(Literal::Void)
 }
+
+fn eval_error(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    if list.len() != 2 {
+        return Err(LiwbError::arity("error", 1, list.len() - 1));
+    }
+    let message = eval_literal(list[1].clone(), env, state)?;
+    Err(LiwbError::raised(match message {
+        Literal::String(s) => s,
+        message => message.to_string(),
+    }))
+}
+
+/// Stop the program with the exit code, from 0 to 255 like process exit codes, 0 by default.
+/// The program unwinds with a LiwbError::Exit, leaving the process to the host.
+fn eval_exit(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let code = match list.len() {
+        1 => 0,
+        2 => {
+            let code = eval_literal(list[1].clone(), env, state)?;
+            let Ok(code) = u8::from_literal(code.clone()) else {
+                return Err(LiwbError::type_mismatch("exit code from 0 to 255", &code));
+            };
+            i32::from(code)
+        }
+        found => return Err(LiwbError::arity("exit", 1, found - 1)),
+    };
+    Err(LiwbError::exit(code))
+}
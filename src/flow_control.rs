@@ -0,0 +1,94 @@
+use crate::environment::Environment;
+use crate::error::LiwbError;
+use crate::evaluator::*;
+use crate::literals::*;
+
+fn eval_condition(
+    condition: Literal,
+    env: &Environment,
+    state: &mut State,
+) -> Result<bool, LiwbError> {
+    let condition = eval_literal(condition, env, state)?;
+    let Literal::Boolean(condition) = condition else {
+        return Err(LiwbError::type_mismatch("boolean", &condition));
+    };
+    Ok(condition)
+}
+
+/// `(cond condition value ... else value)`, returns the value of the first true condition,
+/// or void if none of them is true.
+pub fn eval_cond(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
+    let found = list.len() - 1;
+    if !found.is_multiple_of(2) {
+        return Err(LiwbError::arity("cond", found + 1, found));
+    }
+    let mut list = list.into_iter().skip(1);
+
+    while let (Some(condition), Some(value)) = (list.next(), list.next()) {
+        let is_else = condition == Literal::Symbol("else".to_string());
+        if is_else || eval_condition(condition, env, state)? {
+            return Ok(Step::Tail(value, env.clone()));
+        }
+    }
+    Ok(Step::Done(Literal::Void))
+}
+
+/// `(when condition body...)` evaluates the body if the condition is true,
+/// `(unless condition body...)` if it is false.
+pub fn eval_when(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
+    let found = list.len() - 1;
+    let mut list = list.into_iter();
+    let Some(Literal::Symbol(name)) = list.next() else {
+        unreachable!("when and unless are only dispatched from a symbol");
+    };
+
+    let condition = list
+        .next()
+        .ok_or_else(|| LiwbError::arity(name.as_str(), 1, found))?;
+    let condition = eval_condition(condition, env, state)?;
+
+    if condition == (name == "when") {
+        return eval_body(list.collect(), env, state);
+    }
+    Ok(Step::Done(Literal::Void))
+}
+
+/// `and` stops at the first false condition and `or` at the first true one.
+pub fn eval_logical_operator(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let mut list = list.into_iter();
+    let Some(Literal::Symbol(name)) = list.next() else {
+        unreachable!("and and or are only dispatched from a symbol");
+    };
+
+    let stop_at = name == "or";
+    for condition in list {
+        if eval_condition(condition, env, state)? == stop_at {
+            return Ok(Literal::Boolean(stop_at));
+        }
+    }
+    Ok(Literal::Boolean(!stop_at))
+}
+
+pub fn eval_not(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    if list.len() != 2 {
+        return Err(LiwbError::arity("not", 1, list.len() - 1));
+    }
+    let condition = list.into_iter().nth(1).unwrap_or(Literal::Void);
+    Ok(Literal::Boolean(!eval_condition(condition, env, state)?))
+}
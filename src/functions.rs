@@ -1,85 +1,121 @@
+use crate::environment::Environment;
+use crate::error::LiwbError;
 use crate::evaluator::*;
 use crate::literals::*;
 
-pub fn define_function(list: Vec<Literal>, variables: &mut Variables) -> Result<Literal, String> {
+pub fn define_function(list: Vec<Literal>, env: &Environment) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter().skip(1);
     let name = list
         .next()
-        .ok_or(format!("Error. Could not get the function name"))?;
+        .ok_or_else(|| LiwbError::arity("fn", 3, found))?;
 
     let Literal::Symbol(name) = name else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol to be function name, found: {:?}",
-            name
-        ));
+        return Err(LiwbError::type_mismatch("symbol", &name));
     };
 
     let args = list
         .next()
-        .ok_or(format!("Error. Could not get the function argument name"))?;
+        .ok_or_else(|| LiwbError::arity("fn", 3, found))?;
+    let args = parse_arguments(args)?;
 
-    let Literal::Vector(args) = args else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol to be function argument name, found: {:?}",
-            args
-        ));
+    let body = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("fn", 3, found))?;
+
+    let function = Literal::Function {
+        name: name.clone(),
+        args,
+        body: Box::new(body),
+        env: env.clone(),
     };
 
-    let args = args
-        .into_iter()
-        .filter(|literal| *literal != Literal::Void)
-        .map(|arg| match arg {
-            Literal::Symbol(s) => Ok(s),
-            _ => Err(format!(
-                "Error. Expected Literal::Symbol type for argument name, found: {:?}",
-                arg
-            )),
-        })
-        .collect::<Result<Vec<_>, String>>()?;
+    env.define(name, function);
+    Ok(Literal::Void)
+}
+
+pub fn define_lambda(list: Vec<Literal>, env: &Environment) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
+    let mut list = list.into_iter().skip(1);
+
+    let args = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("lambda", 2, found))?;
+    let args = parse_arguments(args)?;
 
     let body = list
         .next()
-        .ok_or(format!("Error. Missing body of the function."))?;
+        .ok_or_else(|| LiwbError::arity("lambda", 2, found))?;
 
-    let function = Literal::Function {
-        name: name.clone(),
+    Ok(Literal::Function {
+        name: "lambda".to_string(),
         args,
         body: Box::new(body),
+        env: env.clone(),
+    })
+}
+
+fn parse_arguments(args: Literal) -> Result<Vec<String>, LiwbError> {
+    let Literal::Vector(args) = args else {
+        return Err(LiwbError::type_mismatch("vector", &args));
     };
 
-    variables.insert(name, function);
-    return Ok(Literal::Void);
+    args.into_iter()
+        .filter(|literal| *literal != Literal::Void)
+        .map(|arg| match arg {
+            Literal::Symbol(s) => Ok(s),
+            _ => Err(LiwbError::type_mismatch("symbol", &arg)),
+        })
+        .collect::<Result<Vec<_>, LiwbError>>()
 }
 
 pub fn eval_function(
     function: Literal,
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    match call_function(function, list, env, state)? {
+        Step::Done(literal) => Ok(literal),
+        Step::Tail(body, frame) => eval_literal(body, &frame, state),
+    }
+}
+
+/// Evaluate the arguments of a function call. Native functions are called right away,
+/// while for liwb functions the arguments are bound on a new frame, and the body
+/// is returned to be evaluated in it.
+pub fn call_function(
+    function: Literal,
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Step, LiwbError> {
     let values = list
         .into_iter()
-        .map(|literal| eval_literal(literal, variables, deleted))
         .skip(1)
-        .collect::<Result<Vec<_>, String>>()?;
+        .map(|literal| eval_literal(literal, env, state))
+        .collect::<Result<Vec<_>, LiwbError>>()?;
 
-    let Literal::Function { args, body, .. } = function else {
-        return Err(format!("Error. Expected function, found: {:?}", function));
+    let (name, args, body, closure) = match function {
+        Literal::Function {
+            name,
+            args,
+            body,
+            env,
+        } => (name, args, body, env),
+        Literal::NativeFunction(native) => return native.call(&values).map(Step::Done),
+        function => return Err(LiwbError::type_mismatch("function", &function)),
     };
 
     if values.len() != args.len() {
-        return Err(format!(
-            "Error. The number of arguments that the funciton requires differ from the passed"
-        ));
+        return Err(LiwbError::arity(name, args.len(), values.len()));
     }
 
-    let mut local_variables: Variables = variables.clone();
+    let frame = closure.child();
 
-    args.into_iter()
-        .zip(values.into_iter())
-        .for_each(|(key, value)| {
-            local_variables.insert(key, value);
-        });
+    args.into_iter().zip(values).for_each(|(key, value)| {
+        frame.define(key, value);
+    });
 
-    eval_literal(*body, &mut local_variables, deleted)
+    Ok(Step::Tail(*body, frame))
 }
@@ -0,0 +1,187 @@
+use crate::cancellation::CancellationToken;
+use crate::capabilities::Capabilities;
+use crate::environment::{collect_cycles, Environment};
+use crate::error::LiwbError;
+use crate::evaluator::{eval_literal, State};
+use crate::lexer::lexer;
+use crate::limits::Limits;
+use crate::literals::{Literal, NativeFn, NativeFunction};
+use crate::parser::parser;
+use crate::utils::read_file;
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Evaluates liwb code, keeping the global variables and the deleted literals between evaluations,
+/// so a prelude can be loaded once and used by everything evaluated after it.
+#[derive(Default)]
+pub struct Interpreter {
+    globals: Environment,
+    state: State,
+    timeout: Option<Duration>,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluate every literal, returning their values.
+    pub fn eval_literals(&mut self, literals: Vec<Literal>) -> Result<Vec<Literal>, LiwbError> {
+        let mut values = Vec::new();
+        self.eval_each(literals, |value| values.push(value))?;
+        Ok(values)
+    }
+
+    /// Evaluate the literals in order, giving the value of each one to on_value
+    /// as soon as it is evaluated, and stopping at the first error.
+    /// The limits and the timeout apply to all of them together.
+    pub fn eval_each(
+        &mut self,
+        literals: Vec<Literal>,
+        mut on_value: impl FnMut(Literal),
+    ) -> Result<(), LiwbError> {
+        self.state.steps = 0;
+        self.state.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.state.check_cancelled()?;
+        for literal in literals {
+            on_value(eval_literal(literal, &self.globals, &mut self.state)?);
+        }
+        Ok(())
+    }
+
+    /// Evaluate the source, returning the value of the last expression.
+    pub fn eval_str(&mut self, source: &str) -> Result<Literal, LiwbError> {
+        let literals = parser(lexer(source))?;
+        Ok(self.eval_literals(literals)?.pop().unwrap_or(Literal::Void))
+    }
+
+    /// Evaluate the content of a file, returning the value of the last expression.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<Literal, LiwbError> {
+        let source = read_file(path.as_ref())?;
+        self.eval_str(&source)
+    }
+
+    /// Forget every global variable, registered functions included, and every deleted literal.
+    /// The output, limits, capabilities and timeout are kept.
+    pub fn reset(&mut self) {
+        self.globals = Environment::new();
+        self.state.deleted.clear();
+        collect_cycles();
+    }
+
+    /// Send everything printed by the programs to output, instead of the stdout.
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.state.output = Box::new(output);
+    }
+
+    /// Limit the resources used by the programs. The step limit applies to each evaluation.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.state.limits = limits;
+    }
+
+    /// Choose which groups of builtins the programs can use.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.state.capabilities = capabilities;
+    }
+
+    /// A token that stops the running program when cancelled, from any thread.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.state.cancellation.clone()
+    }
+
+    /// Stop each evaluation that runs for longer than timeout.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// The name of every global variable, sorted.
+    pub fn global_names(&self) -> Vec<String> {
+        self.globals.names()
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Literal> {
+        self.globals.get(name)
+    }
+
+    pub fn set_global(&mut self, name: impl Into<String>, literal: Literal) {
+        self.globals.define(name, literal);
+    }
+
+    /// Give the program its arguments, as a vector of strings called argv.
+    /// They belong to the env capability, so nothing is defined when it is disabled.
+    pub fn set_argv(&mut self, argv: Vec<String>) {
+        if self.state.capabilities.env {
+            let argv = argv.into_iter().map(Literal::String).collect();
+            self.set_global("argv", Literal::Vector(argv));
+        }
+    }
+
+    /// Make a Rust function callable from liwb, like any function defined with fn.
+    /// The function receives its arguments already evaluated, and is only called
+    /// with exactly `arity` of them.
+    /// Builtins take precedence over registered functions with the same name.
+    pub fn register_fn<F>(&mut self, name: impl Into<String>, arity: usize, function: F)
+    where
+        F: Fn(&[Literal]) -> Result<Literal, LiwbError> + 'static,
+    {
+        self.register(name.into(), Some(arity), Rc::new(function));
+    }
+
+    /// Like register_fn, but for functions that accept any number of arguments.
+    pub fn register_variadic_fn<F>(&mut self, name: impl Into<String>, function: F)
+    where
+        F: Fn(&[Literal]) -> Result<Literal, LiwbError> + 'static,
+    {
+        self.register(name.into(), None, Rc::new(function));
+    }
+
+    fn register(&mut self, name: String, arity: Option<usize>, function: Rc<NativeFn>) {
+        let native = NativeFunction {
+            name: name.clone(),
+            arity,
+            function,
+        };
+        self.globals.define(name, Literal::NativeFunction(native));
+    }
+}
+
+// The globals hold the functions defined in them, so they are only freed by collecting cycles.
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+/// An output that keeps everything written to it. Clones share the same content,
+/// so one clone can be given to the interpreter and the other used to read what was printed.
+#[derive(Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,28 +1,254 @@
+use crate::span::Span;
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub const UNTERMINATED_STRING: &str = "Unterminated string.";
+pub const UNCLOSED_BLOCK_COMMENT: &str = "Unclosed block comment.";
+pub const NOTHING_TO_DISCARD: &str = "Nothing to discard after #_.";
+
 #[derive(Debug, PartialEq)]
 pub enum Token {
     Symbol(String),
     Number(f64),
+    String(String),
     Lparen,
     Rparen,
     LBracket,
     RBracket,
+    LBrace,
+    RBrace,
+    /// Something that could not be lexed, the parser turns it into a LiwbError::Lex.
+    Error(String),
+}
+
+/// A token together with the place where it was found.
+#[derive(Debug, PartialEq)]
+pub struct Lexeme {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl PartialEq<Token> for Lexeme {
+    fn eq(&self, other: &Token) -> bool {
+        self.token == *other
+    }
 }
 
-pub fn lexer<'a>(source: &'a str) -> Vec<Token> {
-    regex::Regex::new(r#"[^\s\[\]\(\)"']+|(\[|\]|\(|\)|"[^"]*")"#)
-        .unwrap()
-        .find_iter(source)
-        .map(|lexeme| match lexeme.as_str() {
-            "(" => Token::Lparen,
-            ")" => Token::Rparen,
-            "[" => Token::LBracket,
-            "]" => Token::RBracket,
-            lexeme => {
-                if let Ok(number) = lexeme.parse::<f64>() {
-                    return Token::Number(number);
-                }
-                Token::Symbol(lexeme.to_string())
+/// The id of the next source given to the lexer, 0 is kept for spans of no source.
+static NEXT_SOURCE_ID: AtomicUsize = AtomicUsize::new(1);
+
+pub fn lexer(source: &str) -> Vec<Lexeme> {
+    let mut scanner = Scanner {
+        source,
+        source_id: NEXT_SOURCE_ID.fetch_add(1, Ordering::Relaxed),
+        chars: source.char_indices().peekable(),
+        line: 1,
+        line_start: 0,
+    };
+    std::iter::from_fn(|| scanner.next_lexeme()).collect::<Vec<_>>()
+}
+
+#[derive(Clone)]
+struct Scanner<'a> {
+    source: &'a str,
+    source_id: usize,
+    chars: Peekable<CharIndices<'a>>,
+    line: usize,
+    line_start: usize,
+}
+
+impl Scanner<'_> {
+    fn next_lexeme(&mut self) -> Option<Lexeme> {
+        loop {
+            let (start, c) = *self.chars.peek()?;
+            let span = self.span(start);
+            match c {
+                c if c.is_whitespace() || c == '\'' => {
+                    self.advance();
+                }
+                ';' => self.skip_line(),
+                // A shebang line, so scripts can be run directly.
+                '#' if start == 0 && self.source.starts_with("#!") => self.skip_line(),
+                '#' if self.source[start..].starts_with("#|") => {
+                    if let Err(message) = self.skip_block_comment() {
+                        return Some(self.lexeme(Token::Error(message), span));
+                    }
+                }
+                '#' if self.source[start..].starts_with("#_") => {
+                    self.advance();
+                    self.advance();
+                    if let Err(lexeme) = self.skip_form(span) {
+                        return Some(lexeme);
+                    }
+                }
+                '(' | ')' | '[' | ']' | '{' | '}' => {
+                    self.advance();
+                    let token = match c {
+                        '(' => Token::Lparen,
+                        ')' => Token::Rparen,
+                        '[' => Token::LBracket,
+                        ']' => Token::RBracket,
+                        '{' => Token::LBrace,
+                        _ => Token::RBrace,
+                    };
+                    return Some(self.lexeme(token, span));
+                }
+                '"' => {
+                    let token = match self.scan_string(span) {
+                        Ok(string) => Token::String(string),
+                        Err((message, span)) => {
+                            return Some(self.lexeme(Token::Error(message), span))
+                        }
+                    };
+                    return Some(self.lexeme(token, span));
+                }
+                _ => {
+                    while self.chars.peek().is_some_and(|(_, c)| !is_delimiter(*c)) {
+                        self.advance();
+                    }
+                    let text = &self.source[start..self.offset()];
+                    let token = match text.parse::<f64>() {
+                        Ok(number) => Token::Number(number),
+                        Err(_) => Token::Symbol(text.to_string()),
+                    };
+                    return Some(self.lexeme(token, span));
+                }
+            }
+        }
+    }
+
+    /// Scan a string literal, replacing its escape sequences.
+    /// On error, returns the message and the span it refers to.
+    fn scan_string(&mut self, span: Span) -> Result<String, (String, Span)> {
+        self.advance();
+        let mut string = String::new();
+        loop {
+            let Some((start, c)) = self.chars.peek().copied() else {
+                return Err((UNTERMINATED_STRING.to_string(), span));
+            };
+            let escape = self.span(start);
+            self.advance();
+            match c {
+                '"' => return Ok(string),
+                '\\' => string.push(self.scan_escape().ok_or_else(|| {
+                    let sequence = &self.source[start..self.offset()];
+                    (format!("Invalid escape sequence: {sequence}"), escape)
+                })?),
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn scan_escape(&mut self) -> Option<char> {
+        match self.advance()? {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            'u' => {
+                if self.advance()? != '{' {
+                    return None;
+                }
+                let mut digits = String::new();
+                loop {
+                    match self.advance()? {
+                        '}' => break,
+                        c if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                        _ => return None,
+                    }
+                }
+                char::from_u32(u32::from_str_radix(&digits, 16).ok()?)
+            }
+            _ => None,
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.chars.peek().is_some_and(|(_, c)| *c != '\n') {
+            self.advance();
+        }
+    }
+
+    /// Skip a `#| ... |#` comment, which can contain other block comments.
+    fn skip_block_comment(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            let Some((start, _)) = self.chars.peek().copied() else {
+                return Err(UNCLOSED_BLOCK_COMMENT.to_string());
+            };
+            let rest = &self.source[start..];
+            if rest.starts_with("#|") || rest.starts_with("|#") {
+                depth += if rest.starts_with("#|") { 1 } else { -1 };
+                self.advance();
+                self.advance();
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else {
+                self.advance();
             }
-        })
-        .collect::<Vec<_>>()
+        }
+    }
+
+    /// Skip the next form, used by `#_`. A list or vector is skipped until its closing bracket.
+    /// On error, returns the error lexeme, leaving a closing bracket after `#_` to the parser.
+    fn skip_form(&mut self, span: Span) -> Result<(), Lexeme> {
+        let next = self.clone().next_lexeme().map(|lexeme| lexeme.token);
+        if let None | Some(Token::Rparen | Token::RBracket | Token::RBrace) = next {
+            let message = NOTHING_TO_DISCARD.to_string();
+            return Err(self.lexeme(Token::Error(message), span));
+        }
+        let mut depth = 0;
+        while let Some(lexeme) = self.next_lexeme() {
+            match lexeme.token {
+                Token::Lparen | Token::LBracket | Token::LBrace => depth += 1,
+                Token::Rparen | Token::RBracket | Token::RBrace => depth -= 1,
+                Token::Error(_) => return Err(lexeme),
+                _ => {}
+            }
+            if depth <= 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let (index, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.line_start = index + 1;
+        }
+        Some(c)
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|(index, _)| *index)
+            .unwrap_or(self.source.len())
+    }
+
+    fn span(&self, start: usize) -> Span {
+        let column = self.source[self.line_start..start].chars().count() + 1;
+        Span {
+            source: self.source_id,
+            ..Span::new(self.line, column, start, start)
+        }
+    }
+
+    fn lexeme(&mut self, token: Token, span: Span) -> Lexeme {
+        let end = self.offset();
+        Lexeme {
+            token,
+            span: Span { end, ..span },
+        }
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '{' | '}' | '"' | '\'' | ';')
 }
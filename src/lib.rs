@@ -1,9 +1,25 @@
+pub mod cancellation;
+pub mod capabilities;
+pub mod cli;
+pub mod environment;
+pub mod error;
 pub mod evaluator;
+pub mod flow_control;
 pub mod functions;
+pub mod interpreter;
 pub mod lexer;
+pub mod limits;
 pub mod literals;
+pub mod map_manipulation;
 pub mod math_functions;
 pub mod parser;
+pub mod printer;
 pub mod repl;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod session;
+pub mod span;
 pub mod utils;
 pub mod vector_manipulation;
+
+pub use interpreter::{Interpreter, OutputBuffer};
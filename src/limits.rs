@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+/// How many bytes of stack nested evaluations can use by default. Half of the 2 MiB stack
+/// of spawned threads, the smallest a program can run on, leaving room for the caller
+/// and for the builtins evaluating arguments. Hosts on a bigger stack can raise it.
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1024 * 1024;
+
+/// Limits on the resources a program can use, None meaning unlimited.
+/// Exceeding any of them stops the program with a LiwbError::LimitExceeded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Literals evaluated on each call to the interpreter.
+    pub max_steps: Option<u64>,
+    /// How deep evaluations can be nested. Nested lists and function calls that are not
+    /// in tail position add one level each.
+    pub max_depth: Option<usize>,
+    /// Bytes of stack used by nested evaluations, counted from the outermost one.
+    /// Without it, a runaway recursion overflows the stack, so it is the only limit by default.
+    /// Running on a thread with a smaller stack than this can still overflow it.
+    pub max_stack_size: Option<usize>,
+    /// Elements of a vector or map, or characters of a string, built by the program.
+    pub max_collection_size: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: None,
+            max_depth: None,
+            max_stack_size: Some(DEFAULT_MAX_STACK_SIZE),
+            max_collection_size: None,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, only for programs that can be trusted.
+    pub fn unlimited() -> Self {
+        Self {
+            max_steps: None,
+            max_depth: None,
+            max_stack_size: None,
+            max_collection_size: None,
+        }
+    }
+}
+
+/// The limit that was exceeded, with its value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Steps(u64),
+    Depth(usize),
+    StackSize(usize),
+    CollectionSize(usize),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Steps(steps) => write!(f, "the limit of {steps} evaluation steps"),
+            Limit::Depth(depth) => write!(f, "the maximum depth of {depth} nested evaluations"),
+            Limit::StackSize(size) => {
+                write!(
+                    f,
+                    "the maximum of {size} bytes of stack for nested evaluations"
+                )
+            }
+            Limit::CollectionSize(size) => {
+                write!(f, "the maximum size of {size} elements for a collection")
+            }
+        }
+    }
+}
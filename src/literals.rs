@@ -1,4 +1,12 @@
-use std::fmt::Display;
+use crate::environment::Environment;
+use crate::error::LiwbError;
+use crate::span::Span;
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display};
+use std::hash::{BuildHasher, Hash};
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq, Clone, PartialOrd)]
 pub enum MathOperators {
@@ -18,11 +26,15 @@ pub enum Operator {
     NotEqual,
 }
 
-#[derive(Debug, PartialEq, Clone, PartialOrd)]
+#[derive(Debug, Clone)]
 pub enum Literal {
     Void,
-    List(Vec<Literal>),
+    /// A list, with where it was written in the source. Only lists have a span,
+    /// errors in the other literals are reported at the list they were written in.
+    /// The span is not part of the value, lists are compared without it.
+    List(Vec<Literal>, Span),
     Vector(Vec<Literal>),
+    Map(BTreeMap<MapKey, Literal>),
     Number(f64),
     Symbol(String),
     String(String),
@@ -34,7 +46,237 @@ pub enum Literal {
         name: String,
         args: Vec<String>,
         body: Box<Literal>,
+        env: Environment,
     },
+    NativeFunction(NativeFunction),
+}
+
+// Written by hand to leave the spans of lists out, otherwise the same as the derived comparisons:
+// literals of different kinds are ordered by kind, and the rest by their fields in order.
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Literal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Literal::Void, Literal::Void) | (Literal::If, Literal::If) => Some(Ordering::Equal),
+            (Literal::List(left, _), Literal::List(right, _))
+            | (Literal::Vector(left), Literal::Vector(right)) => left.partial_cmp(right),
+            (Literal::Map(left), Literal::Map(right)) => left.partial_cmp(right),
+            (Literal::Number(left), Literal::Number(right)) => left.partial_cmp(right),
+            (Literal::Symbol(left), Literal::Symbol(right))
+            | (Literal::String(left), Literal::String(right)) => left.partial_cmp(right),
+            (Literal::MathOperator(left), Literal::MathOperator(right)) => left.partial_cmp(right),
+            (Literal::Boolean(left), Literal::Boolean(right)) => left.partial_cmp(right),
+            (Literal::BinaryOperator(left), Literal::BinaryOperator(right)) => {
+                left.partial_cmp(right)
+            }
+            (
+                Literal::Function {
+                    name,
+                    args,
+                    body,
+                    env,
+                },
+                Literal::Function {
+                    name: other_name,
+                    args: other_args,
+                    body: other_body,
+                    env: other_env,
+                },
+            ) => (name, args, body, env)
+                .partial_cmp(&(other_name, other_args, other_body, other_env)),
+            (Literal::NativeFunction(left), Literal::NativeFunction(right)) => {
+                left.partial_cmp(right)
+            }
+            _ => self.rank().partial_cmp(&other.rank()),
+        }
+    }
+}
+
+impl Literal {
+    fn rank(&self) -> u8 {
+        match self {
+            Literal::Void => 0,
+            Literal::List(..) => 1,
+            Literal::Vector(_) => 2,
+            Literal::Map(_) => 3,
+            Literal::Number(_) => 4,
+            Literal::Symbol(_) => 5,
+            Literal::String(_) => 6,
+            Literal::MathOperator(_) => 7,
+            Literal::Boolean(_) => 8,
+            Literal::BinaryOperator(_) => 9,
+            Literal::If => 10,
+            Literal::Function { .. } => 11,
+            Literal::NativeFunction(_) => 12,
+        }
+    }
+}
+
+pub type NativeFn = dyn Fn(&[Literal]) -> Result<Literal, LiwbError>;
+
+/// A function written in Rust, called with its arguments already evaluated.
+/// Functions without an arity accept any number of arguments.
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Option<usize>,
+    pub function: Rc<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn call(&self, args: &[Literal]) -> Result<Literal, LiwbError> {
+        if let Some(arity) = self.arity {
+            if args.len() != arity {
+                return Err(LiwbError::arity(self.name.as_str(), arity, args.len()));
+            }
+        }
+        (self.function)(args)
+    }
+}
+
+// Closures can not be compared or printed, so native functions are compared by identity.
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.function, &other.function)
+    }
+}
+
+impl PartialOrd for NativeFunction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+/// The literals that can be used as keys of a map.
+#[derive(Debug, Clone)]
+pub enum MapKey {
+    Number(f64),
+    String(String),
+    Boolean(bool),
+}
+
+// Numbers are compared with total_cmp, so every key has a place on the map, even NaN.
+impl Ord for MapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (MapKey::Number(left), MapKey::Number(right)) => left.total_cmp(right),
+            (MapKey::String(left), MapKey::String(right)) => left.cmp(right),
+            (MapKey::Boolean(left), MapKey::Boolean(right)) => left.cmp(right),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for MapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MapKey {}
+
+impl MapKey {
+    fn rank(&self) -> u8 {
+        match self {
+            MapKey::Number(_) => 0,
+            MapKey::String(_) => 1,
+            MapKey::Boolean(_) => 2,
+        }
+    }
+}
+
+impl TryFrom<Literal> for MapKey {
+    type Error = LiwbError;
+
+    fn try_from(literal: Literal) -> Result<Self, Self::Error> {
+        match literal {
+            Literal::Number(n) => Ok(MapKey::from(n)),
+            Literal::String(s) => Ok(MapKey::String(s)),
+            Literal::Boolean(b) => Ok(MapKey::Boolean(b)),
+            literal => Err(LiwbError::type_mismatch(
+                "number, string or boolean",
+                &literal,
+            )),
+        }
+    }
+}
+
+impl From<MapKey> for Literal {
+    fn from(key: MapKey) -> Self {
+        match key {
+            MapKey::Number(n) => Literal::Number(n),
+            MapKey::String(s) => Literal::String(s),
+            MapKey::Boolean(b) => Literal::Boolean(b),
+        }
+    }
+}
+
+impl Display for MathOperators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MathOperators::Add => "+",
+                MathOperators::Subtract => "-",
+                MathOperators::Multiply => "*",
+                MathOperators::Divide => "/",
+            }
+        )
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Operator::Equal => "=",
+                Operator::LessThan => "<",
+                Operator::BiggerThan => ">",
+                Operator::LessOrEqualThan => "<=",
+                Operator::BiggerOrEqualThan => ">=",
+                Operator::NotEqual => "!=",
+            }
+        )
+    }
+}
+
+impl Literal {
+    /// Name of the literal type, as shown in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Literal::Void => "void",
+            Literal::List(..) => "list",
+            Literal::Vector(_) => "vector",
+            Literal::Map(_) => "map",
+            Literal::Number(_) => "number",
+            Literal::Symbol(_) => "symbol",
+            Literal::String(_) => "string",
+            Literal::MathOperator(_) | Literal::BinaryOperator(_) => "operator",
+            Literal::Boolean(_) => "boolean",
+            Literal::If => "if",
+            Literal::Function { .. } | Literal::NativeFunction(_) => "function",
+        }
+    }
 }
 
 impl Display for Literal {
@@ -45,20 +287,263 @@ impl Display for Literal {
             match self {
                 Literal::Number(n) => n.to_string(),
                 Literal::Boolean(b) => b.to_string(),
-                Literal::String(s) => format!("\"{}\"", s.to_string()),
+                Literal::String(s) => format!("\"{}\"", s),
                 Literal::Symbol(s) => s.to_string(),
                 Literal::Vector(v) => {
                     let mut result = String::from("[ ");
-                    v.into_iter().for_each(|literal| {
-                        result += &format!("{} ", literal.to_string());
+                    v.iter().for_each(|literal| {
+                        result += &format!("{} ", literal);
                     });
                     result += " ]";
                     result
                 }
-                Literal::List(list) => format!("(liwb list#{:?})", list),
+                Literal::Map(map) => {
+                    let mut result = String::from("{ ");
+                    map.iter().for_each(|(key, value)| {
+                        result += &format!("{} {} ", Literal::from(key.clone()), value);
+                    });
+                    result += " }";
+                    result
+                }
+                Literal::List(list, _) => format!("(liwb list#{:?})", list),
                 Literal::Function { name, .. } => format!("(liwb function#{name})"),
-                Literal::Void | _ => "()".to_string(),
+                Literal::NativeFunction(native) =>
+                    format!("(liwb native function#{})", native.name),
+                _ => "()".to_string(),
             }
         )
     }
 }
+
+/// Conversion from a liwb value into a Rust type, failing with a TypeMismatch
+/// when the literal has the wrong type.
+pub trait FromLiteral: Sized {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError>;
+}
+
+/// Conversion from a Rust type into a liwb value.
+pub trait IntoLiteral {
+    fn into_literal(self) -> Literal;
+}
+
+impl FromLiteral for Literal {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        Ok(literal)
+    }
+}
+
+impl IntoLiteral for Literal {
+    fn into_literal(self) -> Literal {
+        self
+    }
+}
+
+impl FromLiteral for f64 {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::Number(n) => Ok(n),
+            literal => Err(LiwbError::type_mismatch("number", &literal)),
+        }
+    }
+}
+
+impl IntoLiteral for f64 {
+    fn into_literal(self) -> Literal {
+        Literal::Number(self)
+    }
+}
+
+impl From<f64> for MapKey {
+    fn from(n: f64) -> Self {
+        // 0 and -0 are the same number, so they must be the same key.
+        MapKey::Number(if n == 0.0 { 0.0 } else { n })
+    }
+}
+
+// Numbers are converted to integers only when no information is lost.
+macro_rules! integer_literal {
+    ($($integer:ty),*) => {
+        $(
+            impl FromLiteral for $integer {
+                fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+                    match literal {
+                        // `as` saturates, so the range has to be checked before converting.
+                        // MAX + 1 is a power of two, so it is exact even when MAX is not.
+                        Literal::Number(n)
+                            if n.fract() == 0.0
+                                && n >= <$integer>::MIN as f64
+                                && n < <$integer>::MAX as f64 + 1.0 =>
+                        {
+                            Ok(n as $integer)
+                        }
+                        literal => Err(LiwbError::type_mismatch("integer", &literal)),
+                    }
+                }
+            }
+
+            impl IntoLiteral for $integer {
+                fn into_literal(self) -> Literal {
+                    Literal::Number(self as f64)
+                }
+            }
+
+            impl From<$integer> for MapKey {
+                fn from(n: $integer) -> Self {
+                    MapKey::Number(n as f64)
+                }
+            }
+        )*
+    };
+}
+
+integer_literal!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromLiteral for bool {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::Boolean(b) => Ok(b),
+            literal => Err(LiwbError::type_mismatch("boolean", &literal)),
+        }
+    }
+}
+
+impl IntoLiteral for bool {
+    fn into_literal(self) -> Literal {
+        Literal::Boolean(self)
+    }
+}
+
+impl From<bool> for MapKey {
+    fn from(b: bool) -> Self {
+        MapKey::Boolean(b)
+    }
+}
+
+impl FromLiteral for String {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::String(s) => Ok(s),
+            literal => Err(LiwbError::type_mismatch("string", &literal)),
+        }
+    }
+}
+
+impl IntoLiteral for String {
+    fn into_literal(self) -> Literal {
+        Literal::String(self)
+    }
+}
+
+impl From<String> for MapKey {
+    fn from(s: String) -> Self {
+        MapKey::String(s)
+    }
+}
+
+impl IntoLiteral for &str {
+    fn into_literal(self) -> Literal {
+        Literal::String(self.to_string())
+    }
+}
+
+impl From<&str> for MapKey {
+    fn from(s: &str) -> Self {
+        MapKey::String(s.to_string())
+    }
+}
+
+impl<T: FromLiteral> FromLiteral for Vec<T> {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::Vector(vector) => vector.into_iter().map(T::from_literal).collect(),
+            literal => Err(LiwbError::type_mismatch("vector", &literal)),
+        }
+    }
+}
+
+impl<T: IntoLiteral> IntoLiteral for Vec<T> {
+    fn into_literal(self) -> Literal {
+        Literal::Vector(self.into_iter().map(T::into_literal).collect())
+    }
+}
+
+/// None is void, like the value of a liwb expression that returns nothing.
+impl<T: FromLiteral> FromLiteral for Option<T> {
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::Void => Ok(None),
+            literal => T::from_literal(literal).map(Some),
+        }
+    }
+}
+
+impl<T: IntoLiteral> IntoLiteral for Option<T> {
+    fn into_literal(self) -> Literal {
+        self.map(T::into_literal).unwrap_or(Literal::Void)
+    }
+}
+
+// Tuples are vectors with a fixed number of elements.
+macro_rules! tuple_literal {
+    ($($length:literal => ($($name:ident),*)),*) => {
+        $(
+            impl<$($name: FromLiteral),*> FromLiteral for ($($name,)*) {
+                fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+                    match literal {
+                        Literal::Vector(vector) if vector.len() == $length => {
+                            let mut vector = vector.into_iter();
+                            Ok(($($name::from_literal(vector.next().unwrap_or(Literal::Void))?,)*))
+                        }
+                        literal => Err(LiwbError::type_mismatch(
+                            concat!("vector of ", $length, " elements"),
+                            &literal,
+                        )),
+                    }
+                }
+            }
+
+            impl<$($name: IntoLiteral),*> IntoLiteral for ($($name,)*) {
+                #[allow(non_snake_case)]
+                fn into_literal(self) -> Literal {
+                    let ($($name,)*) = self;
+                    Literal::Vector(vec![$($name.into_literal()),*])
+                }
+            }
+        )*
+    };
+}
+
+tuple_literal!(
+    1 => (A),
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D),
+    5 => (A, B, C, D, E)
+);
+
+impl<K, V, S> FromLiteral for HashMap<K, V, S>
+where
+    K: FromLiteral + Eq + Hash,
+    V: FromLiteral,
+    S: BuildHasher + Default,
+{
+    fn from_literal(literal: Literal) -> Result<Self, LiwbError> {
+        match literal {
+            Literal::Map(map) => map
+                .into_iter()
+                .map(|(key, value)| Ok((K::from_literal(key.into())?, V::from_literal(value)?)))
+                .collect(),
+            literal => Err(LiwbError::type_mismatch("map", &literal)),
+        }
+    }
+}
+
+impl<K: Into<MapKey>, V: IntoLiteral, S> IntoLiteral for HashMap<K, V, S> {
+    fn into_literal(self) -> Literal {
+        Literal::Map(
+            self.into_iter()
+                .map(|(key, value)| (key.into(), value.into_literal()))
+                .collect(),
+        )
+    }
+}
@@ -1,15 +1,64 @@
-use liwb::evaluator::eval_from_literals;
-use liwb::lexer::lexer;
-use liwb::parser::parser;
+use liwb::cli::*;
+use liwb::error::LiwbError;
 use liwb::repl::repl;
+use liwb::session::Session;
 use liwb::utils::read_file;
 
-fn main() -> Result<(), String> {
-    let Some(file_path) = std::env::args().skip(1).next() else {
-        repl();
+use std::io::{Read, Write};
+
+fn main() {
+    let code = with_stack(run);
+    // Exiting skips destructors, so what was printed must be flushed first.
+    let _ = std::io::stdout().flush();
+    std::process::exit(code);
+}
+
+/// Run what the command line asks for, returning the exit code.
+fn run() -> i32 {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            return EXIT_USAGE_ERROR;
+        }
     };
-    let source = read_file(file_path)?;
-    let literal = parser(lexer(&source))?;
-    let _ = eval_from_literals(literal)?;
-    Ok(())
+    if options.help {
+        println!("{USAGE}");
+        return EXIT_SUCCESS;
+    }
+
+    let mut session = Session::new();
+    session.interpreter.set_limits(limits());
+    session.interpreter.set_argv(options.argv);
+
+    if let Some(source) = options.source {
+        let (name, source) = match source {
+            Source::File(path) => match read_file(&path) {
+                Ok(source) => (path, source),
+                Err(err) => {
+                    eprintln!("{}", err.message());
+                    return EXIT_INPUT_ERROR;
+                }
+            },
+            Source::Expression(expression) => ("<expr>".to_string(), expression),
+            Source::Stdin => {
+                let mut source = String::new();
+                if let Err(err) = std::io::stdin().read_to_string(&mut source) {
+                    eprintln!("Failed to read the stdin: {err}");
+                    return EXIT_INPUT_ERROR;
+                }
+                ("<stdin>".to_string(), source)
+            }
+        };
+        match session.eval_named(&name, &source) {
+            Err(LiwbError::Exit { code, .. }) => return code,
+            Err(err) => {
+                eprintln!("{}", session.render(&err));
+                return exit_code(&err);
+            }
+            Ok(_) if !options.interactive => return EXIT_SUCCESS,
+            Ok(_) => {}
+        }
+    }
+    repl(session, options.session.as_deref())
 }
@@ -0,0 +1,174 @@
+use crate::environment::Environment;
+use crate::error::LiwbError;
+use crate::evaluator::*;
+use crate::literals::*;
+
+use std::collections::BTreeMap;
+
+pub type Map = BTreeMap<MapKey, Literal>;
+
+pub fn eval_map_operation(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let operator = &list[0];
+    let Literal::Symbol(operator) = operator else {
+        return Err(LiwbError::type_mismatch("symbol", operator));
+    };
+    match operator.as_str() {
+        "hash-map" => eval_hash_map(list, env, state),
+        "get" => eval_get(list, env, state),
+        "assoc" => eval_assoc(list, env, state),
+        "dissoc" => eval_dissoc(list, env, state),
+        "keys" | "values" => eval_keys_or_values(list, env, state),
+        "has?" => eval_has(list, env, state),
+        "merge" => eval_merge(list, env, state),
+        operator => Err(LiwbError::unknown_symbol(operator)),
+    }
+}
+
+fn eval_map(literal: Literal, env: &Environment, state: &mut State) -> Result<Map, LiwbError> {
+    let map = eval_literal(literal, env, state)?;
+    let Literal::Map(map) = map else {
+        return Err(LiwbError::type_mismatch("map", &map));
+    };
+    Ok(map)
+}
+
+fn eval_key(literal: Literal, env: &Environment, state: &mut State) -> Result<MapKey, LiwbError> {
+    MapKey::try_from(eval_literal(literal, env, state)?)
+}
+
+/// Insert every key value pair of the list into the map.
+fn insert_pairs(
+    name: &str,
+    mut map: Map,
+    list: impl ExactSizeIterator<Item = Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Map, LiwbError> {
+    if !list.len().is_multiple_of(2) {
+        return Err(LiwbError::arity(name, list.len() + 1, list.len()));
+    }
+    let mut list = list.into_iter();
+    while let (Some(key), Some(value)) = (list.next(), list.next()) {
+        let key = eval_key(key, env, state)?;
+        let value = eval_literal(value, env, state)?;
+        map.insert(key, value);
+        state.check_size(map.len())?;
+    }
+    Ok(map)
+}
+
+fn eval_hash_map(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let list = list.into_iter().skip(1);
+    Ok(Literal::Map(insert_pairs(
+        "hash-map",
+        Map::new(),
+        list,
+        env,
+        state,
+    )?))
+}
+
+fn eval_get(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    if list.len() != 3 && list.len() != 4 {
+        return Err(LiwbError::arity("get", 2, list.len() - 1));
+    }
+    let mut list = list.into_iter().skip(1);
+    let map = eval_map(list.next().unwrap_or(Literal::Void), env, state)?;
+    let key = eval_key(list.next().unwrap_or(Literal::Void), env, state)?;
+
+    match map.get(&key) {
+        Some(value) => Ok(value.clone()),
+        None => eval_literal(list.next().unwrap_or(Literal::Void), env, state),
+    }
+}
+
+fn eval_assoc(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
+    let mut list = list.into_iter().skip(1);
+    let map = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("assoc", 3, found))?;
+    let map = eval_map(map, env, state)?;
+    if found < 3 {
+        return Err(LiwbError::arity("assoc", 3, found));
+    }
+    Ok(Literal::Map(insert_pairs("assoc", map, list, env, state)?))
+}
+
+fn eval_dissoc(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
+    let mut list = list.into_iter().skip(1);
+    let map = list
+        .next()
+        .ok_or_else(|| LiwbError::arity("dissoc", 1, found))?;
+    let mut map = eval_map(map, env, state)?;
+    for key in list {
+        let key = eval_key(key, env, state)?;
+        map.remove(&key);
+    }
+    Ok(Literal::Map(map))
+}
+
+fn eval_keys_or_values(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let operator = list[0].to_string();
+    if list.len() != 2 {
+        return Err(LiwbError::arity(operator, 1, list.len() - 1));
+    }
+    let map = eval_map(list.into_iter().nth(1).unwrap_or(Literal::Void), env, state)?;
+    Ok(Literal::Vector(match operator.as_str() {
+        "keys" => map.into_keys().map(Literal::from).collect(),
+        _ => map.into_values().collect(),
+    }))
+}
+
+fn eval_has(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    if list.len() != 3 {
+        return Err(LiwbError::arity("has?", 2, list.len() - 1));
+    }
+    let mut list = list.into_iter().skip(1);
+    let map = eval_map(list.next().unwrap_or(Literal::Void), env, state)?;
+    let key = eval_key(list.next().unwrap_or(Literal::Void), env, state)?;
+    Ok(Literal::Boolean(map.contains_key(&key)))
+}
+
+/// Merge the maps from left to right, the values of the last maps win.
+fn eval_merge(
+    list: Vec<Literal>,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let mut merged = Map::new();
+    for map in list.into_iter().skip(1) {
+        merged.extend(eval_map(map, env, state)?);
+        state.check_size(merged.len())?;
+    }
+    Ok(Literal::Map(merged))
+}
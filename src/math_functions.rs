@@ -1,30 +1,28 @@
+use crate::environment::Environment;
+use crate::error::LiwbError;
 use crate::evaluator::*;
 use crate::literals::*;
 
 pub fn eval_operator_with_single_arg(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter();
-    let operator = list.next().ok_or(format!("Error. Missing operator"))?;
+    let operator = list.next().unwrap_or(Literal::Void);
 
     let Literal::Symbol(operator) = operator else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol for operator, found: {:?}",
-            operator
-        ));
+        return Err(LiwbError::type_mismatch("symbol", &operator));
     };
 
     let left = list
         .next()
-        .ok_or(format!("Error. Could not get left side for opertion."))?;
+        .ok_or_else(|| LiwbError::arity(operator.as_str(), 1, found))?;
 
-    let Literal::Number(n) = eval_literal(left.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error: Could not complete sqrt evaluation, expected Literal::Number, found {:?}",
-            left
-        ));
+    let left = eval_literal(left, env, state)?;
+    let Literal::Number(n) = left else {
+        return Err(LiwbError::type_mismatch("number", &left));
     };
 
     Ok(Literal::Number(match operator.as_str() {
@@ -43,43 +41,37 @@ pub fn eval_operator_with_single_arg(
 
 pub fn eval_operator_with_double_argument(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter();
-    let operator = list.next().ok_or(format!("Error. Missing operator"))?;
+    let operator = list.next().unwrap_or(Literal::Void);
 
     let Literal::Symbol(operator) = operator else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol for operator, found: {:?}",
-            operator
-        ));
+        return Err(LiwbError::type_mismatch("symbol", &operator));
     };
 
     let left = list
         .next()
-        .ok_or(format!("Error. Could not get left side for opertion."))?;
+        .ok_or_else(|| LiwbError::arity(operator.as_str(), 2, found))?;
 
-    let Literal::Number(left) = eval_literal(left.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error: Could not complete sqrt evaluation, expected Literal::Number, found {:?}",
-            left
-        ));
+    let left = eval_literal(left, env, state)?;
+    let Literal::Number(left) = left else {
+        return Err(LiwbError::type_mismatch("number", &left));
     };
 
     let right = list
         .next()
-        .ok_or(format!("Error. Could not get left side for opertion."))?;
+        .ok_or_else(|| LiwbError::arity(operator.as_str(), 2, found))?;
 
-    let Literal::Number(right) = eval_literal(right.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error: Could not complete sqrt evaluation, expected Literal::Number, found {:?}",
-            right
-        ));
+    let right = eval_literal(right, env, state)?;
+    let Literal::Number(right) = right else {
+        return Err(LiwbError::type_mismatch("number", &right));
     };
 
     match operator.as_str() {
         "mod" => Ok(Literal::Number(left % right)),
-        _ => Err(format!("Error. Unknow type of operator: {operator}")),
+        _ => Err(LiwbError::unknown_symbol(operator)),
     }
 }
@@ -1,28 +1,54 @@
-use crate::lexer::Token;
+use crate::error::LiwbError;
+use crate::lexer::{Lexeme, Token};
 use crate::literals::*;
 
-pub fn parser(tokens: Vec<Token>) -> Result<Vec<Literal>, String> {
+pub fn parser(tokens: Vec<Lexeme>) -> Result<Vec<Literal>, LiwbError> {
     if tokens.is_empty() {
         return Ok(vec![Literal::Void]);
     }
+    if let Some(Lexeme {
+        token: Token::Error(message),
+        span,
+    }) = tokens
+        .iter()
+        .find(|lexeme| matches!(lexeme.token, Token::Error(_)))
+    {
+        return Err(LiwbError::lex(message.as_str(), *span));
+    }
     let mut tokens = tokens.into_iter().peekable();
     let mut literals: Vec<Literal> = Vec::new();
-    while let Some(_) = tokens.peek() {
-        literals.push(parse_tokens(&mut tokens)?);
+    while let Some(lexeme) = tokens.peek() {
+        if lexeme.token == Token::LBrace {
+            literals.push(parse_map(&mut tokens)?);
+        } else {
+            literals.push(parse_tokens(&mut tokens)?);
+        }
         tokens.next();
     }
-    return Ok(literals);
+    Ok(literals)
 }
 
-type PeekableTokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
-fn parse_tokens(tokens: &mut PeekableTokens) -> Result<Literal, String> {
+type PeekableTokens = std::iter::Peekable<std::vec::IntoIter<Lexeme>>;
+fn parse_tokens(tokens: &mut PeekableTokens) -> Result<Literal, LiwbError> {
     let head = tokens.next();
-    let Some(Token::Lparen | Token::LBracket) = head else {
-        return Err(format!("Error: Expected Lparen, found: {:?}", head));
+    let Some(Lexeme {
+        token: opener @ (Token::Lparen | Token::LBracket | Token::LBrace),
+        span: open,
+    }) = head
+    else {
+        let span = head.as_ref().map(|head| head.span).unwrap_or_default();
+        let message = match head.map(|head| head.token) {
+            Some(token @ (Token::Rparen | Token::RBracket | Token::RBrace)) => {
+                format!("Unexpected {}.", describe(&token))
+            }
+            Some(token) => format!("Expected `(`, found {}.", describe(&token)),
+            None => "Expected `(`, found the end of the source.".to_string(),
+        };
+        return Err(LiwbError::parse(message, span));
     };
 
     let mut literals: Vec<Literal> = Vec::new();
-    while let Some(token) = tokens.peek() {
+    while let Some(Lexeme { token, .. }) = tokens.peek() {
         match token {
             Token::Symbol(s) => match s.as_str() {
                 "+" => literals.push(Literal::MathOperator(MathOperators::Add)),
@@ -38,50 +64,90 @@ fn parse_tokens(tokens: &mut PeekableTokens) -> Result<Literal, String> {
                 "true" => literals.push(Literal::Boolean(true)),
                 "false" => literals.push(Literal::Boolean(false)),
                 "if" => literals.push(Literal::If),
-                _ => {
-                    if s.contains("\"") {
-                        if s.chars().into_iter().filter(|c| *c == '"').count() != 2 {
-                            return Err(String::from(
-                                "You have a invalid string literal somewhere, good luck trying to find it!",
-                            ));
-                        }
-                        literals.push(Literal::String(s.replace("\"", "").to_string()));
-                    } else {
-                        literals.push(Literal::Symbol(s.to_string()));
-                    }
-                }
+                _ => literals.push(Literal::Symbol(s.to_string())),
             },
+            Token::String(s) => literals.push(Literal::String(s.to_string())),
             Token::LBracket => {
                 let result = match parse_tokens(tokens)? {
-                    Literal::List(list) => list,
+                    Literal::List(list, _) => list,
                     Literal::Void => vec![],
-                    result => {
-                        return Err(format!(
-                            "Expected LIteral::List or Literal::Void for Vector. found: {:?}",
-                            result
-                        ))
-                    }
+                    _ => unreachable!("brackets are parsed into a list or void"),
                 };
                 literals.push(Literal::Vector(result));
             }
+            Token::LBrace => literals.push(parse_map(tokens)?),
             Token::Number(n) => literals.push(Literal::Number(*n)),
             Token::Lparen => {
                 literals.push(parse_tokens(tokens)?);
             }
-            Token::Rparen | Token::RBracket => break,
+            Token::Rparen | Token::RBracket | Token::RBrace => break,
+            Token::Error(_) => unreachable!("lex errors are reported before parsing"),
         }
         tokens.next();
     }
     let last = tokens.peek();
     match last {
-        Some(Token::Rparen | Token::RBracket) => {
+        Some(Lexeme {
+            token: closer @ (Token::Rparen | Token::RBracket | Token::RBrace),
+            span: close,
+        }) => {
+            let expected = closing(&opener);
+            if *closer != expected {
+                return Err(LiwbError::parse(
+                    format!(
+                        "Expected {} to close the {} at {}:{}, found {}.",
+                        describe(&expected),
+                        describe(&opener),
+                        open.line,
+                        open.column,
+                        describe(closer)
+                    ),
+                    *close,
+                ));
+            }
             if literals.is_empty() {
                 return Ok(Literal::Void);
             }
-            return Ok(Literal::List(literals));
+            Ok(Literal::List(literals, open.to(*close)))
         }
-        _ => Err(format!(
-            "Unclosed parenthesis somewhere. Good luck trying to find it."
-        )),
+        _ => Err(LiwbError::parse("Unclosed parenthesis.", open)),
+    }
+}
+
+/// `{key value ...}` is parsed as `(hash-map key value ...)`.
+fn parse_map(tokens: &mut PeekableTokens) -> Result<Literal, LiwbError> {
+    let open = tokens.peek().map(|open| open.span).unwrap_or_default();
+    let mut map = vec![Literal::Symbol("hash-map".to_string())];
+    match parse_tokens(tokens)? {
+        Literal::List(list, _) => map.extend(list),
+        Literal::Void => {}
+        _ => unreachable!("braces are parsed into a list or void"),
+    };
+    let close = tokens.peek().map(|close| close.span).unwrap_or(open);
+    Ok(Literal::List(map, open.to(close)))
+}
+
+/// The token closing the list, vector or map opened by the opener.
+fn closing(opener: &Token) -> Token {
+    match opener {
+        Token::LBracket => Token::RBracket,
+        Token::LBrace => Token::RBrace,
+        _ => Token::Rparen,
+    }
+}
+
+/// How a token is named in parse errors.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Symbol(symbol) => format!("symbol `{symbol}`"),
+        Token::Number(number) => format!("number `{number}`"),
+        Token::String(string) => format!("string \"{string}\""),
+        Token::Lparen => "`(`".to_string(),
+        Token::Rparen => "`)`".to_string(),
+        Token::LBracket => "`[`".to_string(),
+        Token::RBracket => "`]`".to_string(),
+        Token::LBrace => "`{`".to_string(),
+        Token::RBrace => "`}`".to_string(),
+        Token::Error(message) => message.clone(),
     }
 }
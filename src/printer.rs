@@ -0,0 +1,139 @@
+use crate::literals::{Literal, MapKey};
+
+/// Columns the REPL fits values in before breaking them over several lines.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// How much the elements of a broken vector, list or map are indented.
+const INDENT: usize = 2;
+
+/// Write the literal in liwb syntax, in a single line. Values that can be written
+/// in a program, like numbers, strings, vectors and maps, are read back as themselves.
+pub fn to_source(literal: &Literal) -> String {
+    match literal {
+        Literal::Void => "()".to_string(),
+        Literal::Number(n) => n.to_string(),
+        Literal::Boolean(b) => b.to_string(),
+        Literal::String(s) => quote(s),
+        Literal::Symbol(s) => s.to_string(),
+        Literal::MathOperator(operator) => operator.to_string(),
+        Literal::BinaryOperator(operator) => operator.to_string(),
+        Literal::If => "if".to_string(),
+        Literal::Vector(vector) => format!("[{}]", join(vector)),
+        Literal::List(list, _) => format!("({})", join(list)),
+        Literal::Map(map) => {
+            let pairs = map
+                .iter()
+                .map(|(key, value)| format!("{} {}", key_to_source(key), to_source(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", pairs.join(" "))
+        }
+        Literal::Function { name, args, .. } => format!("#<fn {name} [{}]>", args.join(" ")),
+        Literal::NativeFunction(native) => format!("#<native fn {}>", native.name),
+    }
+}
+
+/// Like to_source, but vectors, lists and maps that don't fit in width columns
+/// are written with each element on its own line.
+pub fn pretty(literal: &Literal, width: usize) -> String {
+    let mut out = String::new();
+    write_pretty(literal, 0, 0, width, &mut out);
+    out
+}
+
+/// Write the literal starting at column, with its broken lines indented by indent.
+fn write_pretty(literal: &Literal, indent: usize, column: usize, width: usize, out: &mut String) {
+    let flat = to_source(literal);
+    if column + flat.chars().count() <= width {
+        out.push_str(&flat);
+        return;
+    }
+    let (open, close, elements): (&str, &str, Vec<(Option<String>, &Literal)>) = match literal {
+        Literal::Vector(vector) => ("[", "]", vector.iter().map(|l| (None, l)).collect()),
+        Literal::List(list, _) => ("(", ")", list.iter().map(|l| (None, l)).collect()),
+        Literal::Map(map) => (
+            "{",
+            "}",
+            map.iter()
+                .map(|(key, value)| (Some(key_to_source(key)), value))
+                .collect(),
+        ),
+        _ => {
+            out.push_str(&flat);
+            return;
+        }
+    };
+
+    let inner = indent + INDENT;
+    out.push_str(open);
+    if elements
+        .iter()
+        .all(|(key, element)| key.is_none() && !is_collection(element))
+    {
+        // Elements that can't be broken are packed into as few lines as possible.
+        let mut column = None;
+        for (_, element) in elements {
+            let element = to_source(element);
+            let length = element.chars().count();
+            match column {
+                Some(line) if line + 1 + length <= width => {
+                    out.push(' ');
+                    column = Some(line + 1 + length);
+                }
+                _ => {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(inner));
+                    column = Some(inner + length);
+                }
+            }
+            out.push_str(&element);
+        }
+    } else {
+        for (key, element) in elements {
+            out.push('\n');
+            out.push_str(&" ".repeat(inner));
+            let mut column = inner;
+            if let Some(key) = key {
+                column += key.chars().count() + 1;
+                out.push_str(&key);
+                out.push(' ');
+            }
+            write_pretty(element, inner, column, width, out);
+        }
+    }
+    out.push('\n');
+    out.push_str(&" ".repeat(indent));
+    out.push_str(close);
+}
+
+fn is_collection(literal: &Literal) -> bool {
+    matches!(
+        literal,
+        Literal::Vector(_) | Literal::List(..) | Literal::Map(_)
+    )
+}
+
+fn join(literals: &[Literal]) -> String {
+    literals.iter().map(to_source).collect::<Vec<_>>().join(" ")
+}
+
+fn key_to_source(key: &MapKey) -> String {
+    to_source(&Literal::from(key.clone()))
+}
+
+/// Quote the string, escaping it like the lexer reads it.
+fn quote(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -1,54 +1,331 @@
-use crate::evaluator::{eval_literal, Variables};
-use crate::lexer::lexer;
-use crate::literals::Literal;
+use crate::error::LiwbError;
+use crate::evaluator::builtin_names;
+use crate::lexer::{lexer, Token, NOTHING_TO_DISCARD, UNCLOSED_BLOCK_COMMENT, UNTERMINATED_STRING};
+use crate::literals::{Literal, NativeFunction};
 use crate::parser::parser;
+use crate::printer::{pretty, to_source, DEFAULT_WIDTH};
+use crate::session::{Session, REPL_FILE_NAME, SESSION_FILE_NAME};
+use crate::utils::read_file;
 
-use std::collections::HashMap;
-use std::io::Write;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
 
-const PROMPT_MESSAGE: &'static str = "liwb> ";
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-pub fn repl() -> ! {
-    let mut variables: Variables = HashMap::new();
-    let mut deleted_literals: Vec<Literal> = Vec::new();
+const PROMPT_MESSAGE: &str = "liwb> ";
+const CONTINUATION_PROMPT_MESSAGE: &str = "  ... ";
+const HISTORY_FILE_NAME: &str = ".liwb_history";
+const COMMAND_PREFIX: &str = ":";
+const HELP_MESSAGE: &str = "\
+:load <file>      evaluate a file into the session
+:env              list the global variables and functions
+:reset            forget every variable and deleted literal
+:save [file]      write the definitions of the session to a file
+:restore [file]   evaluate a saved session into this one
+:type <expr>      show the type of the value of expr
+:time <expr>      evaluate expr and show how long it took
+:ast <expr>       show the literals expr is parsed into
+:help             show this message";
 
-    let mut eval_from_literals = |literals: Vec<Literal>| -> Result<Vec<Literal>, String> {
-        let mut results: Vec<Literal> = Vec::new();
-        for literal in literals.into_iter() {
-            results.push(eval_literal(
-                literal,
-                &mut variables,
-                &mut deleted_literals,
-            )?);
+/// Run the REPL on the session until the input ends, with Ctrl-D. Ctrl-C discards the current input.
+/// A session file is restored when the REPL starts, if it exists, and saved when it ends.
+/// Returns the exit code of the process, given by the exit function or 0.
+pub fn repl(mut session: Session, session_file: Option<&Path>) -> i32 {
+    if let Some(path) = session_file.filter(|path| path.exists()) {
+        if let Err(err) = restore(&mut session, path) {
+            eprintln!("{err}");
         }
-        Ok(results)
-    };
+    }
+    let mut editor =
+        Editor::<ReplHelper, DefaultHistory>::new().expect("Failed to start the REPL.");
+    editor.set_helper(Some(ReplHelper::default()));
 
+    if let Some(helper) = editor.helper_mut() {
+        helper.globals = session.interpreter.global_names();
+    }
+
+    let history = home_file(HISTORY_FILE_NAME);
+    if let Some(history) = &history {
+        // There is no history to load the first time the REPL runs.
+        let _ = editor.load_history(history);
+    }
+
+    let mut source = String::new();
+    let mut exit_code = 0;
     'repl_loop: loop {
-        print!("{}", PROMPT_MESSAGE);
-        std::io::stdout()
-            .flush()
-            .expect("Failed to flush the stdout.");
-
-        let mut buf = String::new();
-        std::io::stdin()
-            .read_line(&mut buf)
-            .expect("Failed to read the input.");
-
-        let source = buf.trim();
-        let literals = match parser(lexer(source)) {
-            Ok(literals) => literals,
-            Err(err) => {
-                eprintln!("{err}");
+        let prompt = match source.is_empty() {
+            true => PROMPT_MESSAGE,
+            false => CONTINUATION_PROMPT_MESSAGE,
+        };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                source.push_str(&line);
+                source.push('\n');
+            }
+            Err(ReadlineError::Interrupted) => {
+                source.clear();
                 continue 'repl_loop;
             }
-        };
-        match eval_from_literals(literals) {
+            Err(ReadlineError::Eof) => break 'repl_loop,
+            Err(err) => {
+                eprintln!("Failed to read the input: {err}");
+                break 'repl_loop;
+            }
+        }
+
+        if source.trim().is_empty() {
+            source.clear();
+            continue 'repl_loop;
+        }
+        if is_incomplete(&source) {
+            continue 'repl_loop;
+        }
+
+        let input = std::mem::take(&mut source);
+        let _ = editor.add_history_entry(input.trim_end());
+        if let Some(command) = input.trim().strip_prefix(COMMAND_PREFIX) {
+            match run_command(command, &mut session) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => println!("{output}"),
+                Err(err) => eprintln!("{err}"),
+            }
+            if let Some(helper) = editor.helper_mut() {
+                helper.globals = session.interpreter.global_names();
+            }
+            continue 'repl_loop;
+        }
+        let result = session.eval(&input);
+        if let Some(helper) = editor.helper_mut() {
+            helper.globals = session.interpreter.global_names();
+        }
+        match result {
+            Err(LiwbError::Exit { code, .. }) => {
+                exit_code = code;
+                break 'repl_loop;
+            }
             Err(err) => {
-                eprintln!("{err}");
+                eprintln!("{}", session.render(&err));
                 continue 'repl_loop;
             }
-            Ok(result) => println!("{:?}", result),
+            Ok(results) => {
+                let results = format_results(&results);
+                if !results.is_empty() {
+                    println!("{results}");
+                }
+            }
+        }
+    }
+
+    if let Some(history) = &history {
+        if let Err(err) = editor.save_history(history) {
+            eprintln!("Failed to save the history: {err}");
+        }
+    }
+    if let Some(path) = session_file {
+        if let Err(err) = session.save(path) {
+            eprintln!("Failed to save the session: {err}");
+        }
+    }
+    exit_code
+}
+
+/// Run a REPL command, given without its colon, returning what should be printed.
+/// Errors are returned already rendered.
+pub fn run_command(command: &str, session: &mut Session) -> Result<String, String> {
+    let (name, argument) = command
+        .trim()
+        .split_once(char::is_whitespace)
+        .unwrap_or((command.trim(), ""));
+    let argument = argument.trim();
+    let usage = |usage: &str| Err(format!("Usage: {COMMAND_PREFIX}{name} {usage}"));
+    let session_file = || match argument {
+        "" => home_file(SESSION_FILE_NAME).ok_or_else(|| {
+            format!("No home directory to keep the session in, use {COMMAND_PREFIX}{name} <file>")
+        }),
+        path => Ok(PathBuf::from(path)),
+    };
+
+    // Everything is evaluated through the session, so its definitions are saved with it.
+    match name {
+        "load" if argument.is_empty() => usage("<file>"),
+        "load" => {
+            let source = read_file(argument).map_err(|err| err.to_string())?;
+            session
+                .eval_named(argument, &source)
+                .map_err(|err| session.render(&err))?;
+            Ok(String::new())
+        }
+        "env" => {
+            let interpreter = &session.interpreter;
+            Ok(interpreter
+                .global_names()
+                .into_iter()
+                .filter_map(|name| Some(describe_global(&name, interpreter.get_global(&name)?)))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "reset" => {
+            session.reset();
+            Ok(String::new())
+        }
+        "save" => {
+            let path = session_file()?;
+            session.save(&path).map_err(|err| err.to_string())?;
+            Ok(format!("Saved the session to {}.", path.display()))
+        }
+        "restore" => {
+            restore(session, &session_file()?)?;
+            Ok(String::new())
+        }
+        "type" | "time" | "ast" if argument.is_empty() => usage("<expr>"),
+        "type" => {
+            let mut literals = session.eval(argument).map_err(|err| session.render(&err))?;
+            Ok(literals
+                .pop()
+                .unwrap_or(Literal::Void)
+                .type_name()
+                .to_string())
+        }
+        "time" => {
+            let start = Instant::now();
+            let literals = session.eval(argument).map_err(|err| session.render(&err))?;
+            let elapsed = format!("Evaluated in {:?}.", start.elapsed());
+            match format_results(&literals) {
+                results if results.is_empty() => Ok(elapsed),
+                results => Ok(format!("{results}\n{elapsed}")),
+            }
+        }
+        "ast" => {
+            let literals =
+                parser(lexer(argument)).map_err(|err| err.render(REPL_FILE_NAME, argument))?;
+            Ok(literals
+                .iter()
+                .map(|literal| format!("{literal:?}"))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "help" => Ok(HELP_MESSAGE.to_string()),
+        name => Err(format!(
+            "Unknown command {COMMAND_PREFIX}{name}, try {COMMAND_PREFIX}help."
+        )),
+    }
+}
+
+/// Evaluate a saved session into the session, returning the rendered error if it fails.
+pub fn restore(session: &mut Session, path: &Path) -> Result<(), String> {
+    let source = read_file(path).map_err(|err| err.to_string())?;
+    session
+        .eval_named(&path.display().to_string(), &source)
+        .map_err(|err| session.render(&err))?;
+    Ok(())
+}
+
+/// The results of an evaluation in liwb syntax, one per line.
+/// Void results, like the ones of definitions, are left out.
+pub fn format_results(results: &[Literal]) -> String {
+    results
+        .iter()
+        .filter(|result| **result != Literal::Void)
+        .map(|result| pretty(result, DEFAULT_WIDTH))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A line of :env, with the arguments of functions or the value of variables.
+fn describe_global(name: &str, literal: Literal) -> String {
+    match literal {
+        Literal::Function { args, .. } => format!("{name}: function [{}]", args.join(" ")),
+        Literal::NativeFunction(NativeFunction {
+            arity: Some(arity), ..
+        }) => format!(
+            "{name}: native function of {arity} argument{}",
+            if arity == 1 { "" } else { "s" }
+        ),
+        Literal::NativeFunction(_) => {
+            format!("{name}: native function of any number of arguments")
+        }
+        literal => format!("{name}: {} {}", literal.type_name(), to_source(&literal)),
+    }
+}
+
+/// Whether the source ends inside a list, vector, map, string or block comment,
+/// or right after a `#_`, so the REPL should keep reading lines before evaluating it.
+/// Extra closing brackets make it complete, letting the parser report them.
+pub fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    let mut lexemes = lexer(source).into_iter().peekable();
+    while let Some(lexeme) = lexemes.next() {
+        match lexeme.token {
+            Token::Error(message) if message == NOTHING_TO_DISCARD && lexemes.peek().is_none() => {
+                return true
+            }
+            Token::Lparen | Token::LBracket | Token::LBrace => depth += 1,
+            Token::Rparen | Token::RBracket | Token::RBrace if depth == 0 => return false,
+            Token::Rparen | Token::RBracket | Token::RBrace => depth -= 1,
+            Token::Error(message) => {
+                return message == UNTERMINATED_STRING || message == UNCLOSED_BLOCK_COMMENT
+            }
+            _ => {}
         }
     }
+    depth > 0
+}
+
+/// The builtins and globals starting with the word before the cursor,
+/// together with where that word starts.
+pub fn completions(line: &str, pos: usize, globals: &[String]) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .rfind(|c: char| c.is_whitespace() || "()[]{}\"';".contains(c))
+        .map_or(0, |delimiter| delimiter + 1);
+    let word = &line[start..pos];
+    if word.is_empty() {
+        return (start, Vec::new());
+    }
+    let mut candidates = builtin_names()
+        .map(str::to_string)
+        .chain(globals.iter().cloned())
+        .filter(|name| name.starts_with(word))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+fn home_file(name: &str) -> Option<PathBuf> {
+    Some(home::home_dir()?.join(name))
+}
+
+#[derive(Default)]
+struct ReplHelper {
+    /// The globals of the interpreter, updated after each evaluation.
+    globals: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(completions(line, pos, &self.globals))
+    }
 }
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
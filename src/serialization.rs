@@ -0,0 +1,137 @@
+//! Serde support for liwb values, enabled with the `serde` feature.
+//!
+//! | liwb     | serde data model                      |
+//! |----------|---------------------------------------|
+//! | `()`     | unit (`null` in JSON)                 |
+//! | number   | f64, integers are read as numbers too |
+//! | string   | string                                |
+//! | boolean  | bool                                  |
+//! | vector   | sequence                              |
+//! | map      | map                                   |
+//!
+//! Functions, symbols, operators and unevaluated lists have no data representation,
+//! so serializing them is an error. Formats like JSON only have string keys,
+//! so every map read from them has string keys.
+
+use crate::literals::{Literal, MapKey};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+impl Serialize for Literal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Literal::Void => serializer.serialize_unit(),
+            Literal::Number(n) => serializer.serialize_f64(*n),
+            Literal::String(s) => serializer.serialize_str(s),
+            Literal::Boolean(b) => serializer.serialize_bool(*b),
+            Literal::Vector(vector) => {
+                let mut seq = serializer.serialize_seq(Some(vector.len()))?;
+                for literal in vector {
+                    seq.serialize_element(literal)?;
+                }
+                seq.end()
+            }
+            Literal::Map(map) => {
+                let mut serialized = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    serialized.serialize_entry(key, value)?;
+                }
+                serialized.end()
+            }
+            literal => Err(ser::Error::custom(format!(
+                "Can not serialize a {}: {}",
+                literal.type_name(),
+                literal
+            ))),
+        }
+    }
+}
+
+impl Serialize for MapKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            MapKey::Number(n) => serializer.serialize_f64(*n),
+            MapKey::String(s) => serializer.serialize_str(s),
+            MapKey::Boolean(b) => serializer.serialize_bool(*b),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Literal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LiteralVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for MapKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let literal = Literal::deserialize(deserializer)?;
+        MapKey::try_from(literal).map_err(de::Error::custom)
+    }
+}
+
+struct LiteralVisitor;
+
+impl<'de> Visitor<'de> for LiteralVisitor {
+    type Value = Literal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a number, string, boolean, vector, map or ()")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Literal, E> {
+        Ok(Literal::Void)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Literal, E> {
+        Ok(Literal::Void)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Literal, D::Error> {
+        Literal::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Literal, E> {
+        Ok(Literal::Boolean(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Literal, E> {
+        Ok(Literal::Number(n as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Literal, E> {
+        Ok(Literal::Number(n as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Literal, E> {
+        Ok(Literal::Number(n))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Literal, E> {
+        Ok(Literal::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Literal, E> {
+        Ok(Literal::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Literal, A::Error> {
+        let mut vector = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(literal) = seq.next_element()? {
+            vector.push(literal);
+        }
+        Ok(Literal::Vector(vector))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Literal, A::Error> {
+        let mut map = BTreeMap::new();
+        while let Some((key, value)) = access.next_entry::<MapKey, Literal>()? {
+            map.insert(key, value);
+        }
+        Ok(Literal::Map(map))
+    }
+}
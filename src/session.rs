@@ -0,0 +1,102 @@
+use crate::error::LiwbError;
+use crate::interpreter::Interpreter;
+use crate::lexer::lexer;
+use crate::literals::Literal;
+use crate::parser::parser;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Where the REPL saves and restores sessions by default, in the home directory.
+pub const SESSION_FILE_NAME: &str = ".liwb_session.liwb";
+/// The name of the sources evaluated without a name, like the lines of the REPL.
+pub const REPL_FILE_NAME: &str = "<repl>";
+
+/// An interpreter that remembers the definitions evaluated in it,
+/// so they can be saved as a script and restored later.
+#[derive(Default)]
+pub struct Session {
+    pub interpreter: Interpreter,
+    /// The define and fn forms evaluated, as they were written.
+    definitions: Vec<String>,
+    /// The name and text of every source evaluated, by the id of their spans,
+    /// so errors in functions defined by an earlier source are rendered against it.
+    sources: HashMap<usize, (String, String)>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluate a line of the REPL, see eval_named.
+    pub fn eval(&mut self, source: &str) -> Result<Vec<Literal>, LiwbError> {
+        self.eval_named(REPL_FILE_NAME, source)
+    }
+
+    /// Evaluate the source, remembering each definition as soon as it is evaluated,
+    /// so the ones before a failing form are kept like the interpreter keeps them.
+    /// Redefinitions are kept too, since a definition can use the previous value of its name.
+    /// Errors are rendered with the name by render.
+    pub fn eval_named(&mut self, name: &str, source: &str) -> Result<Vec<Literal>, LiwbError> {
+        let lexemes = lexer(source);
+        if let Some(lexeme) = lexemes.first() {
+            let source = (name.to_string(), source.to_string());
+            self.sources.insert(lexeme.span.source, source);
+        }
+        let literals = parser(lexemes)?;
+        let mut definitions = literals
+            .iter()
+            .map(|literal| definition(literal, source))
+            .collect::<Vec<_>>()
+            .into_iter();
+        let mut results = Vec::new();
+        self.interpreter.eval_each(literals, |value| {
+            self.definitions.extend(definitions.next().flatten());
+            results.push(value);
+        })?;
+        Ok(results)
+    }
+
+    /// The definitions of the session as a liwb script, in the order they were evaluated.
+    pub fn script(&self) -> String {
+        self.definitions
+            .iter()
+            .map(|definition| format!("{definition}\n"))
+            .collect()
+    }
+
+    /// Render the error against the source it comes from, which can be an earlier one
+    /// when the error is in a function defined there.
+    pub fn render(&self, err: &LiwbError) -> String {
+        match self.sources.get(&err.span().source) {
+            Some((name, source)) => err.render(name, source),
+            None => err.to_string(),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LiwbError> {
+        std::fs::write(path, self.script())?;
+        Ok(())
+    }
+
+    /// Forget every variable and definition.
+    pub fn reset(&mut self) {
+        self.interpreter.reset();
+        self.definitions.clear();
+        self.sources.clear();
+    }
+}
+
+fn definition(literal: &Literal, source: &str) -> Option<String> {
+    let Literal::List(list, span) = literal else {
+        return None;
+    };
+    let [Literal::Symbol(form), Literal::Symbol(_), ..] = list.as_slice() else {
+        return None;
+    };
+    if form != "define" && form != "fn" {
+        return None;
+    }
+    Some(source.get(span.start..span.end)?.to_string())
+}
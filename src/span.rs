@@ -0,0 +1,78 @@
+/// Position of a piece of source code.
+/// Lines and columns start at 1, the byte offsets are relative to the start of the source.
+/// A span with line 0 was created at runtime and does not point to any source.
+/// Spans are kept on errors, lexemes and lists, but not on the other literals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+    /// Id of the source the span is in, different for each call to the lexer. 0 when unknown.
+    pub source: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, start: usize, end: usize) -> Self {
+        Self {
+            line,
+            column,
+            start,
+            end,
+            source: 0,
+        }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.line != 0
+    }
+
+    /// Span going from the start of self to the end of other.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+
+    /// Render the span as `file:line:col` followed by the source line and a caret under the span.
+    /// When the span is not in the source, only `file:line:col` is rendered.
+    pub fn render(&self, file: &str, source: &str, message: &str) -> String {
+        if !self.is_known() {
+            return format!("{file}: {message}");
+        }
+        if !self.fits(source) {
+            return format!("{file}:{}:{}: {message}", self.line, self.column);
+        }
+        let line_start = source[..self.start]
+            .rfind('\n')
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map(|index| line_start + index)
+            .unwrap_or(source.len());
+        let excerpt = &source[line_start..line_end];
+        let width = source[self.start..self.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        format!(
+            "{file}:{}:{}: {message}\n{gutter} |\n{number} | {excerpt}\n{gutter} | {}{}",
+            self.line,
+            self.column,
+            " ".repeat(self.column - 1),
+            "^".repeat(width)
+        )
+    }
+
+    /// Whether the span can be in the source: its offsets are char boundaries of the source,
+    /// and the start is on the line of the span.
+    fn fits(&self, source: &str) -> bool {
+        self.start <= self.end
+            && source.get(self.start..self.end).is_some()
+            && source[..self.start].matches('\n').count() + 1 == self.line
+    }
+}
@@ -1,10 +1,12 @@
+use crate::error::LiwbError;
+
 use std::io::{ErrorKind, Read};
 
-pub fn read_file(path: impl Into<std::path::PathBuf>) -> Result<String, String> {
+pub fn read_file(path: impl Into<std::path::PathBuf>) -> Result<String, LiwbError> {
     let path: std::path::PathBuf = path.into();
 
     if path.is_dir() {
-        return Err(format!("Expected a file, found a directory."));
+        return Err(LiwbError::io("Expected a file, found a directory."));
     }
 
     let mut file = std::fs::OpenOptions::new()
@@ -12,16 +14,19 @@ pub fn read_file(path: impl Into<std::path::PathBuf>) -> Result<String, String>
         .open(&path)
         .map_err(|err| {
             let path = path.to_str().unwrap();
-            match err.kind() {
+            LiwbError::io(match err.kind() {
                 ErrorKind::NotFound => format!("No such file or directory: {path}."),
                 ErrorKind::PermissionDenied => {
                     format!("Could not open {path}. Permission Denied.")
                 }
                 _ => format!("An unknown type of error ocurred: {err}"),
-            }
+            })
         })?;
     let mut content = String::new();
-    file.read_to_string(&mut content)
-        .map_err(|err| format!("Could not read the file content due a unknown error: {err}"))?;
+    file.read_to_string(&mut content).map_err(|err| {
+        LiwbError::io(format!(
+            "Could not read the file content due a unknown error: {err}"
+        ))
+    })?;
     Ok(content)
 }
@@ -1,86 +1,92 @@
+use crate::environment::Environment;
+use crate::error::LiwbError;
 use crate::evaluator::*;
+use crate::functions::eval_function;
 use crate::literals::*;
+use crate::map_manipulation::Map;
 
 pub fn eval_vector_operation(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     let operator = &list[0];
     let Literal::Symbol(operator) = operator else {
-        return Err(format!(
-            "Error. Expected a Literal::Symbol for the vector operation, found: {:?}",
-            operator
-        ));
+        return Err(LiwbError::type_mismatch("symbol", operator));
     };
     match operator.as_str() {
-        "nth" => eval_nth(list, variables, deleted),
-        "join" => eval_join(list, variables, deleted),
-        "range" => eval_range(list, variables, deleted),
-        "map" => eval_map(list, variables, deleted),
-        "filter" => eval_filter(list, variables, deleted),
-        operator => return Err(format!("Unknow type of vector operation {operator}")),
+        "nth" => eval_nth(list, env, state),
+        "join" => eval_join(list, env, state),
+        "range" => eval_range(list, env, state),
+        "map" => eval_map(list, env, state),
+        "filter" => eval_filter(list, env, state),
+        operator => Err(LiwbError::unknown_symbol(operator)),
     }
 }
 
 fn eval_nth(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     if list.len() != 3 {
-        return Err(format!("Missing arguments for nth"));
+        return Err(LiwbError::arity("nth", 2, list.len() - 1));
     }
     let mut list = list.into_iter().skip(1);
-    let vector_name = list
-        .next()
-        .ok_or(format!("Error. Could not get the name of the vector!"))?;
+    let vector_name = list.next().unwrap_or(Literal::Void);
 
-    let index = list
-        .next()
-        .ok_or(format!("Error. Could not get the index!"))?;
+    let index = list.next().unwrap_or(Literal::Void);
 
-    let Ok(Literal::Number(index)) = eval_literal(index.clone(), variables, deleted) else {
-        return Err(format!(
-            "Error. Expected Literal::Number for index, found: {:?}",
-            index
-        ));
+    let index = eval_literal(index, env, state)?;
+    let Literal::Number(index) = index else {
+        return Err(LiwbError::type_mismatch("number", &index));
     };
 
-    let v = eval_literal(vector_name.clone(), variables, deleted)?;
-    let Literal::Vector(v) = v else {
-        return Err(format!("Error. Expected Literal::Vector, found: {:?}", v));
+    let v = eval_literal(vector_name.clone(), env, state)?;
+    let Literal::Vector(mut v) = v else {
+        return Err(LiwbError::type_mismatch("vector", &v));
     };
-    let index: usize = index.round() as usize;
-    let literal = match v.into_iter().nth(index).unwrap_or(Literal::Void) {
-        Literal::Symbol(s) => Literal::Symbol(s),
-        literal => eval_literal(literal, variables, deleted)?,
+    let index = index.round() as i64;
+    let Some(position) = usize::try_from(index).ok().filter(|index| *index < v.len()) else {
+        return Err(LiwbError::index_out_of_bounds(index, v.len()));
     };
-    Ok(literal)
+    eval_element(v.swap_remove(position), env, state)
+}
+
+/// Evaluate an element taken out of a vector. Vectors store variable names, not their values,
+/// so symbols are returned as they are.
+pub fn eval_element(
+    literal: Literal,
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    match literal {
+        Literal::Symbol(s) => Ok(Literal::Symbol(s)),
+        literal => eval_literal(literal, env, state),
+    }
 }
 
 fn eval_join(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter().skip(1);
     let vector_name = list
         .next()
-        .ok_or(format!("Error. Could not get the name of the vector!"))?;
-    let vector = eval_literal(vector_name, variables, deleted)?;
+        .ok_or_else(|| LiwbError::arity("join", 1, found))?;
+    let vector = eval_literal(vector_name, env, state)?;
     let Literal::Vector(vector) = vector else {
-        return Err(format!(
-            "Error. Expected join fist argument to bet Literal::Vector, found: {:?}",
-            vector
-        ));
+        return Err(LiwbError::type_mismatch("vector", &vector));
     };
 
     let list = list
-        .map(|literal| eval_literal(literal, variables, deleted))
-        .collect::<Result<Vec<_>, String>>()?
+        .map(|literal| eval_literal(literal, env, state))
+        .collect::<Result<Vec<_>, LiwbError>>()?
         .into_iter();
 
+    state.check_size(vector.len() + list.len())?;
     Ok(Literal::Vector(
         vector.into_iter().chain(list).collect::<Vec<_>>(),
     ))
@@ -88,37 +94,33 @@ fn eval_join(
 
 fn eval_range(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
+    let found = list.len() - 1;
     let mut list = list.into_iter().skip(1);
     let start = eval_literal(
         list.next()
-            .ok_or(format!("Error. Missing start agurment for range function."))?,
-        variables,
-        deleted,
+            .ok_or_else(|| LiwbError::arity("range", 2, found))?,
+        env,
+        state,
     )?;
     let Literal::Number(start) = start else {
-        return Err(format!(
-            "Error. Expected start to be Literal::Number, found: {:?}",
-            start
-        ));
+        return Err(LiwbError::type_mismatch("number", &start));
     };
 
     let end = eval_literal(
         list.next()
-            .ok_or(format!("Error. Missing end agurment for range function."))?,
-        variables,
-        deleted,
+            .ok_or_else(|| LiwbError::arity("range", 2, found))?,
+        env,
+        state,
     )?;
     let Literal::Number(end) = end else {
-        return Err(format!(
-            "Error. Expected end to be Literal::Number, found: {:?}",
-            start
-        ));
+        return Err(LiwbError::type_mismatch("number", &end));
     };
     let start = start as i64;
     let end = end as i64;
+    state.check_size(end.saturating_sub(start).saturating_add(1).max(0) as usize)?;
     let range = (start..=end)
         .map(|number| Literal::Number(number as f64))
         .collect::<Vec<_>>();
@@ -128,106 +130,80 @@ fn eval_range(
 
 fn eval_map(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     if list.len() != 3 {
-        return Err(format!(
-            "Erro. Wrong number of arguments passed to map. Expected 3, found {}",
-            list.len()
-        ));
+        return Err(LiwbError::arity("map", 2, list.len() - 1));
     }
     let mut list = list.into_iter().skip(1);
 
-    let function_name = list
-        .next()
-        .ok_or(format!("Error. Missing function parameter for map."))?;
-
-    let Literal::Symbol(s) = &function_name else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol for function name in map, found: {:?}",
-            function_name
-        ));
-    };
-    let function = variables.get(s);
-    let Some(Literal::Function { .. }) = function else {
-        return Err(format!(
-            "Error. Expected Literal::Function, found: {:?}",
-            function
-        ));
+    let function = eval_literal(list.next().unwrap_or(Literal::Void), env, state)?;
+    let (Literal::Function { .. } | Literal::NativeFunction(_)) = function else {
+        return Err(LiwbError::type_mismatch("function", &function));
     };
 
-    let vector = list
-        .next()
-        .ok_or(format!("Error. Missing vector parameter for map."))?;
+    let vector = list.next().unwrap_or(Literal::Void);
 
-    let Literal::Vector(vector) = eval_literal(vector.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error. Expected Literal::Vector, found: {:?}",
-            vector
-        ));
+    // A map is iterated as key value pairs, which are both passed to the function.
+    let arguments: Vec<Vec<Literal>> = match eval_literal(vector, env, state)? {
+        Literal::Vector(vector) => vector.into_iter().map(|literal| vec![literal]).collect(),
+        Literal::Map(map) => map
+            .into_iter()
+            .map(|(key, value)| vec![Literal::from(key), value])
+            .collect(),
+        vector => return Err(LiwbError::type_mismatch("vector", &vector)),
     };
 
     Ok(Literal::Vector(
-        vector
+        arguments
             .into_iter()
-            .map(|parameter| {
-                let list = Literal::List(vec![function_name.clone(), parameter]);
-                eval_literal(list, variables, deleted)
+            .map(|parameters| {
+                let list = [vec![function.clone()], parameters].concat();
+                eval_function(function.clone(), list, env, state)
             })
             .filter(|literal| Ok(Literal::Void) != *literal)
-            .collect::<Result<Vec<_>, String>>()?,
+            .collect::<Result<Vec<_>, LiwbError>>()?,
     ))
 }
 
 fn eval_filter(
     list: Vec<Literal>,
-    variables: &mut Variables,
-    deleted: &mut Vec<Literal>,
-) -> Result<Literal, String> {
+    env: &Environment,
+    state: &mut State,
+) -> Result<Literal, LiwbError> {
     if list.len() != 3 {
-        return Err(format!(
-            "Erro. Wrong number of arguments passed to filter. Expected 3, found {}",
-            list.len()
-        ));
+        return Err(LiwbError::arity("filter", 2, list.len() - 1));
     }
     let mut list = list.into_iter().skip(1);
 
-    let function_name = list
-        .next()
-        .ok_or(format!("Error. Missing function parameter for map."))?;
-
-    let Literal::Symbol(s) = &function_name else {
-        return Err(format!(
-            "Error. Expected Literal::Symbol for function name in filter, found: {:?}",
-            function_name
-        ));
-    };
-    let function = variables.get(s);
-    let Some(Literal::Function { .. }) = function else {
-        return Err(format!(
-            "Error. Expected Literal::Function, found: {:?}",
-            function
-        ));
+    let function = eval_literal(list.next().unwrap_or(Literal::Void), env, state)?;
+    let (Literal::Function { .. } | Literal::NativeFunction(_)) = function else {
+        return Err(LiwbError::type_mismatch("function", &function));
     };
 
-    let vector = list
-        .next()
-        .ok_or(format!("Error. Missing vector parameter for filter."))?;
-
-    let Literal::Vector(vector) = eval_literal(vector.clone(), variables, deleted)? else {
-        return Err(format!(
-            "Error. Expected Literal::Vector, found: {:?}",
-            vector
-        ));
+    let vector = list.next().unwrap_or(Literal::Void);
+
+    let vector = eval_literal(vector, env, state)?;
+    if let Literal::Map(map) = vector {
+        let mut filtered = Map::new();
+        for (key, value) in map {
+            let list = vec![function.clone(), Literal::from(key.clone()), value.clone()];
+            if Literal::Boolean(true) == eval_function(function.clone(), list, env, state)? {
+                filtered.insert(key, value);
+            }
+        }
+        return Ok(Literal::Map(filtered));
+    }
+    let Literal::Vector(vector) = vector else {
+        return Err(LiwbError::type_mismatch("vector", &vector));
     };
-    Ok(Literal::Vector(
-        vector
-            .into_iter()
-            .filter(|parameter| {
-                let list = Literal::List(vec![function_name.clone(), parameter.clone()]);
-                Ok(Literal::Boolean(true)) == eval_literal(list, variables, deleted)
-            })
-            .collect::<Vec<_>>(),
-    ))
+    let mut filtered = Vec::new();
+    for parameter in vector {
+        let list = vec![function.clone(), parameter.clone()];
+        if Literal::Boolean(true) == eval_function(function.clone(), list, env, state)? {
+            filtered.push(parameter);
+        }
+    }
+    Ok(Literal::Vector(filtered))
 }
@@ -0,0 +1,80 @@
+use liwb::cli::*;
+use liwb::Interpreter;
+
+use std::path::PathBuf;
+
+fn parse(args: &[&str]) -> Result<Options, String> {
+    parse_args(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn without_arguments_only_the_repl_starts() {
+    assert_eq!(parse(&[]), Ok(Options::default()));
+    assert_eq!(
+        parse(&["--session", "work.liwb"]),
+        Ok(Options {
+            session: Some(PathBuf::from("work.liwb")),
+            ..Options::default()
+        })
+    );
+}
+
+#[test]
+fn arguments_after_the_program_are_its_argv() {
+    assert_eq!(
+        parse(&["main.liwb", "-e", "x"]),
+        Ok(Options {
+            source: Some(Source::File("main.liwb".to_string())),
+            argv: vec!["-e".to_string(), "x".to_string()],
+            ..Options::default()
+        })
+    );
+    assert_eq!(
+        parse(&["-i", "-e", "(print 1)", "a"]),
+        Ok(Options {
+            source: Some(Source::Expression("(print 1)".to_string())),
+            argv: vec!["a".to_string()],
+            interactive: true,
+            ..Options::default()
+        })
+    );
+    assert_eq!(parse(&["-"]).unwrap().source, Some(Source::Stdin));
+}
+
+#[test]
+fn bad_arguments_are_errors() {
+    assert_eq!(
+        parse(&["-e"]),
+        Err("Expected an expression after -e.".to_string())
+    );
+    assert_eq!(
+        parse(&["--session"]),
+        Err("Expected a file after --session.".to_string())
+    );
+    assert!(parse(&["--nope"])
+        .unwrap_err()
+        .starts_with("Unknown option: --nope."));
+}
+
+#[test]
+fn errors_have_their_own_exit_codes() {
+    let mut interpreter = Interpreter::new();
+    let mut code = |source: &str| exit_code(&interpreter.eval_str(source).unwrap_err());
+    assert_eq!(code("(print (+ 1 2)"), EXIT_SYNTAX_ERROR);
+    assert_eq!(code("(print \"unterminated)"), EXIT_SYNTAX_ERROR);
+    assert_eq!(code("(cube 2)"), EXIT_RUNTIME_ERROR);
+    assert_eq!(code("(error \"failed\")"), EXIT_RUNTIME_ERROR);
+    assert_eq!(code("(exit 42)"), 42);
+}
+
+#[test]
+fn deep_recursion_works_on_the_stack_of_the_command() {
+    let sum = with_stack(|| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_limits(limits());
+        interpreter
+            .eval_str("(fn sum [n] (if (= n 0) 0 (+ n (sum (- n 1))))) (sum 10000)")
+            .map(|sum| sum.to_string())
+    });
+    assert_eq!(sum, Ok("50005000".to_string()));
+}
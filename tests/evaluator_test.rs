@@ -1,9 +1,15 @@
+use liwb::error::LiwbError;
 use liwb::evaluator::eval_from_literals;
 use liwb::lexer::*;
 use liwb::literals::*;
 use liwb::parser::*;
+use liwb::span::Span;
 use liwb::utils::*;
 
+fn eval_error(source: &str) -> LiwbError {
+    eval_from_literals(parser(lexer(source)).unwrap()).unwrap_err()
+}
+
 #[test]
 fn simple_arithemtic_operation() {
     let literals = parser(lexer("(+ (* 5 (- 5 2)) (/ 12 3))")).unwrap();
@@ -169,11 +175,14 @@ fn vector_variable() {
                 Literal::String("two".to_string()),
                 Literal::Number(3.0),
                 Literal::String("four".to_string()),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Add),
-                    Literal::Number(4.0),
-                    Literal::Number(1.0),
-                ])
+                Literal::List(
+                    vec![
+                        Literal::MathOperator(MathOperators::Add),
+                        Literal::Number(4.0),
+                        Literal::Number(1.0),
+                    ],
+                    Span::default(),
+                )
             ])
         ]
     );
@@ -196,16 +205,20 @@ fn get_element_of_vector() {
 
 #[test]
 fn out_of_bounds_vector_index() {
+    // Out of bounds indexes used to return void, they are errors hosts can match on now.
     let source = read_file("liwb/out_of_bounds_vector_index.liwb").unwrap();
     let literals = parser(lexer(&source)).unwrap();
+    assert!(matches!(
+        eval_from_literals(literals),
+        Err(LiwbError::IndexOutOfBounds {
+            index: 10,
+            length: 0,
+            ..
+        })
+    ));
     assert_eq!(
-        eval_from_literals(literals)
-            .unwrap()
-            .into_iter()
-            .rev()
-            .take(1)
-            .collect::<Vec<_>>(),
-        vec![Literal::Void],
+        eval_error("(nth [2 4 6] -1)").message(),
+        "Index -1 is out of bounds for a vector of length 3."
     );
 }
 
@@ -272,7 +285,7 @@ fn vectors() {
             .unwrap()
             .into_iter()
             .rev()
-            .take(6)
+            .take(4)
             .collect::<Vec<_>>(),
         vec![
             Literal::Vector((1..=10).map(|n| Literal::Number(n as f64)).collect()),
@@ -286,8 +299,6 @@ fn vectors() {
                     Literal::Number(6.0),
                 ])
             ]),
-            Literal::Number(2.0),
-            Literal::Void,
             Literal::Number(4.0),
             Literal::Number(5.0),
         ],
@@ -397,3 +408,264 @@ fn deleting_keyword() {
     let literals = parser(lexer(&source)).unwrap();
     eval_from_literals(literals).unwrap();
 }
+
+#[test]
+fn runtime_errors_point_to_the_failing_list() {
+    let source = "(define x 10)\n(print (+ x \"one\"))";
+    let err = eval_from_literals(parser(lexer(source)).unwrap()).unwrap_err();
+    assert_eq!((err.span().line, err.span().column), (2, 8));
+    assert!(err
+        .render("main.liwb", source)
+        .ends_with("2 | (print (+ x \"one\"))\n  |        ^^^^^^^^^^^"));
+}
+
+#[test]
+fn errors_can_be_matched_by_kind() {
+    assert!(matches!(
+        eval_error("(+ 1 undefined-thing)"),
+        LiwbError::UnknownSymbol { name, .. } if name == "undefined-thing"
+    ));
+    assert!(matches!(
+        eval_error("(+ 1 \"one\")"),
+        LiwbError::TypeMismatch {
+            expected: "number",
+            found: "string",
+            ..
+        }
+    ));
+    assert!(matches!(
+        eval_error("(fn add [a b] (+ a b)) (add 1)"),
+        LiwbError::Arity {
+            expected: 2,
+            found: 1,
+            ..
+        }
+    ));
+    assert!(matches!(
+        eval_error("(delete 1) (define x (+ 1 2))"),
+        LiwbError::DeletedLiteral { .. }
+    ));
+    assert!(matches!(
+        eval_error("(error \"something went wrong\")"),
+        LiwbError::Raised { message, .. } if message == "something went wrong"
+    ));
+    assert!(matches!(
+        eval_error("(print +)"),
+        LiwbError::TypeMismatch {
+            expected: "value",
+            found: "operator",
+            ..
+        }
+    ));
+    assert!(matches!(
+        eval_error("(define x (if true + 1))"),
+        LiwbError::TypeMismatch {
+            expected: "value",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn closures() {
+    let source = read_file("liwb/closures.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(4)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![
+            Literal::Number(6.0),
+            Literal::Number(11.0),
+            Literal::Number(101.0),
+            Literal::String("global".to_string()),
+        ],
+    );
+}
+
+#[test]
+fn let_bindings() {
+    let source = read_file("liwb/let.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(4)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![
+            Literal::Number(3.0),
+            Literal::Number(36.0),
+            Literal::Number(5.0),
+            Literal::String("global".to_string()),
+        ],
+    );
+
+    assert!(matches!(
+        eval_error("(let [x 1] x) (+ x 1)"),
+        LiwbError::UnknownSymbol { name, .. } if name == "x"
+    ));
+    assert_eq!(
+        eval_error("(let [[x y z] [1 2]] x)").message(),
+        "Can not destructure a vector of 2 elements into 3 names."
+    );
+    assert!(matches!(eval_error("(let [x] x)"), LiwbError::Arity { .. }));
+}
+
+#[test]
+fn flow_control() {
+    let source = read_file("liwb/flow_control.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(10)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![
+            Literal::String("negative".to_string()),
+            Literal::String("zero".to_string()),
+            Literal::Void,
+            Literal::Number(21.0),
+            Literal::Void,
+            Literal::Boolean(true),
+            Literal::Boolean(true),
+            Literal::Boolean(false),
+            Literal::Boolean(false),
+            Literal::String("done".to_string()),
+        ],
+    );
+
+    assert!(matches!(eval_error("(cond true)"), LiwbError::Arity { .. }));
+    assert!(matches!(
+        eval_error("(and true 1)"),
+        LiwbError::TypeMismatch {
+            expected: "boolean",
+            found: "number",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn maps() {
+    let source = read_file("liwb/maps.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    let map = |pairs: Vec<(Literal, Literal)>| {
+        Literal::Map(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (MapKey::try_from(key).unwrap(), value))
+                .collect(),
+        )
+    };
+    let string = |s: &str| Literal::String(s.to_string());
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(10)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![
+            Literal::Number(25.0),
+            Literal::Void,
+            Literal::Number(0.0),
+            Literal::Boolean(true),
+            Literal::Vector(vec![string("ana"), string("bob"), string("carl")]),
+            Literal::Vector(vec![Literal::Number(25.0)]),
+            map(vec![
+                (Literal::Number(1.0), Literal::Boolean(true)),
+                (string("ana"), Literal::Number(32.0)),
+                (string("bob"), Literal::Number(25.0)),
+            ]),
+            map(vec![(string("ana"), Literal::Number(31.0))]),
+            Literal::Vector(vec![Literal::Number(11.0), Literal::Number(22.0)]),
+            map(vec![(Literal::Number(0.0), string("still zero"))]),
+        ],
+    );
+
+    assert!(matches!(
+        eval_error("(define m {[1] 2})"),
+        LiwbError::TypeMismatch {
+            expected: "number, string or boolean",
+            found: "vector",
+            ..
+        }
+    ));
+    assert!(matches!(
+        eval_error("(define m {1})"),
+        LiwbError::Arity { .. }
+    ));
+    assert!(matches!(
+        eval_error("(get [1 2] 0)"),
+        LiwbError::TypeMismatch {
+            expected: "map",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn lambda() {
+    let source = read_file("liwb/lambda.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(5)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![
+            Literal::Vector(vec![
+                Literal::Number(2.0),
+                Literal::Number(4.0),
+                Literal::Number(6.0),
+            ]),
+            Literal::Vector(vec![Literal::Number(2.0), Literal::Number(3.0)]),
+            Literal::Number(81.0),
+            Literal::Number(50.0),
+            Literal::Number(6.0),
+        ],
+    );
+}
+
+#[test]
+fn tail_calls_do_not_count_as_nested() {
+    let source =
+        "(fn count-down [n] (if (= n 0) \"done\" (count-down (- n 1)))) (count-down 10000)";
+    assert_eq!(
+        eval_from_literals(parser(lexer(source)).unwrap())
+            .unwrap()
+            .pop(),
+        Some(Literal::String("done".to_string()))
+    );
+}
+
+#[test]
+#[ignore = "loops a million times, which takes seconds without optimizations; run with --ignored"]
+fn tail_calls_run_in_constant_stack() {
+    let source = read_file("liwb/tail_calls.liwb").unwrap();
+    let literals = parser(lexer(&source)).unwrap();
+    assert_eq!(
+        eval_from_literals(literals)
+            .unwrap()
+            .into_iter()
+            .rev()
+            .take(2)
+            .rev()
+            .collect::<Vec<_>>(),
+        vec![Literal::Number(1000000.0), Literal::Boolean(false)],
+    );
+}
@@ -0,0 +1,418 @@
+use liwb::capabilities::*;
+use liwb::environment::{collect_cycles, live_scopes};
+use liwb::error::LiwbError;
+use liwb::limits::*;
+use liwb::literals::*;
+use liwb::{Interpreter, OutputBuffer};
+
+use std::time::Duration;
+
+#[test]
+fn state_is_kept_between_evaluations() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(fn square [x] (* x x)) (define two 2)")
+        .unwrap();
+    assert_eq!(
+        interpreter.eval_str("(square (+ two 1))"),
+        Ok(Literal::Number(9.0))
+    );
+    assert_eq!(interpreter.eval_str(""), Ok(Literal::Void));
+}
+
+#[test]
+fn globals_can_be_read_and_written() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("name", Literal::String("liwb".to_string()));
+    interpreter
+        .eval_str("(define greeting (str \"hi \" name))")
+        .unwrap();
+    assert_eq!(
+        interpreter.get_global("name"),
+        Some(Literal::String("liwb".to_string()))
+    );
+    assert!(interpreter.get_global("greeting").is_some());
+    assert_eq!(interpreter.get_global("missing"), None);
+
+    interpreter.eval_str("(let [local 1] local)").unwrap();
+    assert_eq!(interpreter.get_global("local"), None);
+}
+
+#[test]
+fn files_are_loaded_into_the_globals() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.load_file("liwb/closures.liwb"),
+        Ok(Literal::String("global".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_str("(add-five 2)"),
+        Ok(Literal::Number(7.0))
+    );
+    assert!(matches!(
+        interpreter.load_file("liwb/missing.liwb"),
+        Err(LiwbError::Io { .. })
+    ));
+}
+
+#[test]
+fn errors_do_not_reset_the_interpreter() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("(define x 1)").unwrap();
+    assert!(matches!(
+        interpreter.eval_str("(+ x"),
+        Err(LiwbError::Parse { .. })
+    ));
+    assert!(matches!(
+        interpreter.eval_str("(+ x y)"),
+        Err(LiwbError::UnknownSymbol { .. })
+    ));
+    assert_eq!(interpreter.eval_str("(+ x 1)"), Ok(Literal::Number(2.0)));
+}
+
+#[test]
+fn native_functions_are_called_like_liwb_functions() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_fn("double", 1, |args: &[Literal]| match &args[0] {
+        Literal::Number(n) => Ok(Literal::Number(n * 2.0)),
+        literal => Err(LiwbError::type_mismatch("number", literal)),
+    });
+    interpreter.register_variadic_fn("count-args", |args: &[Literal]| {
+        Ok(Literal::Number(args.len() as f64))
+    });
+
+    assert_eq!(
+        interpreter.eval_str("(double (+ 1 2))"),
+        Ok(Literal::Number(6.0))
+    );
+    assert_eq!(
+        interpreter.eval_str("(map double [1 2])"),
+        Ok(Literal::Vector(vec![
+            Literal::Number(2.0),
+            Literal::Number(4.0)
+        ]))
+    );
+    assert_eq!(
+        interpreter.eval_str("(define twice double) (twice 5)"),
+        Ok(Literal::Number(10.0))
+    );
+    assert_eq!(
+        interpreter.eval_str("(count-args) (count-args 1 \"two\" [3])"),
+        Ok(Literal::Number(3.0))
+    );
+    assert!(matches!(
+        interpreter.get_global("double"),
+        Some(Literal::NativeFunction(NativeFunction {
+            arity: Some(1),
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn native_function_errors_point_to_the_call() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_fn("double", 1, |args: &[Literal]| match &args[0] {
+        Literal::Number(n) => Ok(Literal::Number(n * 2.0)),
+        literal => Err(LiwbError::type_mismatch("number", literal)),
+    });
+
+    let error = interpreter.eval_str("(double 1 2)").unwrap_err();
+    assert!(matches!(
+        error,
+        LiwbError::Arity {
+            expected: 1,
+            found: 2,
+            ..
+        }
+    ));
+    let error = interpreter
+        .eval_str("(define x 1)\n(double \"one\")")
+        .unwrap_err();
+    assert!(matches!(error, LiwbError::TypeMismatch { .. }));
+    assert_eq!((error.span().line, error.span().column), (2, 1));
+}
+
+#[test]
+fn output_can_be_captured() {
+    let mut interpreter = Interpreter::new();
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+
+    interpreter
+        .eval_str("(print 1 \"two\") (fn show [x] (print x)) (show (+ 1 2))")
+        .unwrap();
+    assert_eq!(output.contents(), "1\n\"two\"\n3\n");
+
+    output.clear();
+    interpreter.eval_str("(print [1 2])").unwrap();
+    assert_eq!(output.contents(), "[ 1 2  ]\n");
+}
+
+#[test]
+fn runaway_recursion_does_not_overflow_a_spawned_thread() {
+    // Spawned threads have a 2 MiB stack.
+    let thread = std::thread::spawn(|| {
+        Interpreter::new()
+            .eval_str("(fn forever [n] (+ 1 (forever n))) (forever 1)")
+            .unwrap_err()
+    });
+    assert!(matches!(
+        thread.join().unwrap(),
+        LiwbError::LimitExceeded { .. }
+    ));
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(fn forever [n] (+ 1 (forever n)))")
+        .unwrap();
+    let error = interpreter.eval_str("(forever 1)").unwrap_err();
+    assert!(matches!(
+        error,
+        LiwbError::LimitExceeded {
+            limit: Limit::StackSize(DEFAULT_MAX_STACK_SIZE),
+            ..
+        }
+    ));
+
+    interpreter.set_limits(Limits {
+        max_depth: Some(50),
+        ..Limits::default()
+    });
+    assert!(matches!(
+        interpreter.eval_str("(forever 1)"),
+        Err(LiwbError::LimitExceeded {
+            limit: Limit::Depth(50),
+            ..
+        })
+    ));
+    interpreter
+        .eval_str("(fn count [n] (if (= n 0) 0 (+ 1 (count (- n 1)))))")
+        .unwrap();
+    assert_eq!(
+        interpreter.eval_str("(count 10)"),
+        Ok(Literal::Number(10.0))
+    );
+}
+
+#[test]
+fn step_budget_applies_to_each_evaluation() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        max_steps: Some(1_000),
+        ..Limits::default()
+    });
+    interpreter
+        .eval_str("(fn loop [n] (if (= n 0) \"done\" (loop (- n 1))))")
+        .unwrap();
+    assert_eq!(
+        interpreter.eval_str("(loop 10)"),
+        Ok(Literal::String("done".to_string()))
+    );
+    assert_eq!(
+        interpreter.eval_str("(loop 10)"),
+        Ok(Literal::String("done".to_string()))
+    );
+    let error = interpreter.eval_str("(loop 1000000)").unwrap_err();
+    assert_eq!(
+        error.message(),
+        "Exceeded the limit of 1000 evaluation steps."
+    );
+}
+
+#[test]
+fn collections_can_not_grow_past_the_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        max_collection_size: Some(100),
+        ..Limits::default()
+    });
+    assert!(interpreter.eval_str("(range 1 100)").is_ok());
+    let limit_exceeded = |result: Result<Literal, LiwbError>| {
+        matches!(
+            result,
+            Err(LiwbError::LimitExceeded {
+                limit: Limit::CollectionSize(100),
+                ..
+            })
+        )
+    };
+    assert!(limit_exceeded(
+        interpreter.eval_str("(range 0 1000000000000)")
+    ));
+    assert!(limit_exceeded(
+        interpreter.eval_str("(join (range 1 100) 101)")
+    ));
+    assert!(limit_exceeded(interpreter.eval_str(
+        "(fn grow [m n] (if (= n 0) m (grow (assoc m n n) (- n 1)))) (grow {} 101)"
+    )));
+}
+
+#[test]
+fn evaluations_can_be_cancelled_from_another_thread() {
+    let mut interpreter = Interpreter::new();
+    let token = interpreter.cancellation_token();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        token.cancel();
+    });
+    let result = interpreter.eval_str("(fn forever [n] (forever (+ n 1))) (forever 0)");
+    canceller.join().unwrap();
+    assert!(matches!(result, Err(LiwbError::Cancelled { .. })));
+    assert!(matches!(
+        interpreter.eval_str("(+ 1 2)"),
+        Err(LiwbError::Cancelled { .. })
+    ));
+
+    interpreter.cancellation_token().reset();
+    assert_eq!(interpreter.eval_str("(+ 1 2)"), Ok(Literal::Number(3.0)));
+}
+
+#[test]
+fn evaluations_can_time_out() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_timeout(Some(Duration::from_millis(50)));
+    let error = interpreter
+        .eval_str("(fn forever [n] (forever (+ n 1))) (forever 0)")
+        .unwrap_err();
+    assert_eq!(error.message(), "The evaluation was cancelled.");
+    assert!(matches!(
+        interpreter.eval_str("(forever 0)"),
+        Err(LiwbError::Cancelled { .. })
+    ));
+    assert_eq!(interpreter.eval_str("(+ 1 2)"), Ok(Literal::Number(3.0)));
+}
+
+#[test]
+fn disabled_capabilities_are_unknown_symbols() {
+    let mut interpreter = Interpreter::new();
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_capabilities(Capabilities::pure());
+
+    assert!(matches!(
+        interpreter.eval_str("(print 42)"),
+        Err(LiwbError::UnknownSymbol { .. })
+    ));
+    assert_eq!(
+        interpreter.eval_str("(nth (map (lambda [x] (* x x)) [1 2 3]) 2)"),
+        Ok(Literal::Number(9.0))
+    );
+    assert_eq!(
+        interpreter.eval_str("(str 1 2)"),
+        Ok(Literal::String("12".to_string()))
+    );
+
+    interpreter.register_fn("print", 1, |_| Ok(Literal::Boolean(true)));
+    assert_eq!(
+        interpreter.eval_str("(print 42)"),
+        Ok(Literal::Boolean(true))
+    );
+    assert_eq!(output.contents(), "");
+
+    interpreter.set_capabilities(Capabilities::all());
+    interpreter.eval_str("(print 42)").unwrap();
+    assert_eq!(output.contents(), "42\n");
+}
+
+#[test]
+fn the_pure_profile_can_not_exit_or_read_argv() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_capabilities(Capabilities::pure());
+    interpreter.set_argv(vec!["secret".to_string()]);
+    assert!(matches!(
+        interpreter.eval_str("(exit 1)"),
+        Err(LiwbError::UnknownSymbol { .. })
+    ));
+    assert!(matches!(
+        interpreter.eval_str("(nth argv 0)"),
+        Err(LiwbError::UnknownSymbol { name, .. }) if name == "argv"
+    ));
+
+    interpreter.set_capabilities(Capabilities::all());
+    interpreter.set_argv(vec!["a".to_string()]);
+    assert_eq!(
+        interpreter.eval_str("(nth argv 0)"),
+        Ok(Literal::String("a".to_string()))
+    );
+}
+
+#[test]
+fn exit_stops_the_program_with_its_code() {
+    let mut interpreter = Interpreter::new();
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    let error = interpreter
+        .eval_str("(print 1) (exit (+ 1 2)) (print 2)")
+        .unwrap_err();
+    assert!(matches!(error, LiwbError::Exit { code: 3, .. }));
+    assert_eq!(output.contents(), "1\n");
+    assert!(matches!(
+        interpreter.eval_str("(exit)"),
+        Err(LiwbError::Exit { code: 0, .. })
+    ));
+    assert!(matches!(
+        interpreter.eval_str("(exit 1.5)"),
+        Err(LiwbError::TypeMismatch { .. })
+    ));
+    assert!(matches!(
+        interpreter.eval_str("(exit 255)"),
+        Err(LiwbError::Exit { code: 255, .. })
+    ));
+    for code in ["300", "256", "-1"] {
+        assert_eq!(
+            interpreter
+                .eval_str(&format!("(exit {code})"))
+                .unwrap_err()
+                .message(),
+            "Expected exit code from 0 to 255, found number."
+        );
+    }
+    assert!(matches!(
+        interpreter.eval_str("(exit 1 2)"),
+        Err(LiwbError::Arity { .. })
+    ));
+}
+
+#[test]
+fn exit_inside_filter_stops_the_program() {
+    let mut interpreter = Interpreter::new();
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    let error = interpreter
+        .eval_str("(filter (lambda [x] (exit 7)) [1 2]) (print \"after\")")
+        .unwrap_err();
+    assert!(matches!(error, LiwbError::Exit { code: 7, .. }));
+    assert_eq!(output.contents(), "");
+    assert!(matches!(
+        interpreter.eval_str("(filter (lambda [x] (error \"bad\")) [1 2])"),
+        Err(LiwbError::Raised { .. })
+    ));
+}
+
+#[test]
+fn scopes_kept_alive_by_closures_are_freed() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(fn loop [n] (if (= n 0) 0 (loop (let [f (lambda [x] x)] (- n 1)))))")
+        .unwrap();
+    interpreter.eval_str("(loop 100000)").unwrap();
+    assert!(live_scopes() < 5000);
+    collect_cycles();
+    assert_eq!(live_scopes(), 1);
+
+    interpreter
+        .eval_str("(fn make-adder [n] (lambda [x] (+ x n))) (define add5 (make-adder 5))")
+        .unwrap();
+    let adder = interpreter.get_global("add5").unwrap();
+    interpreter.reset();
+    assert_eq!(live_scopes(), 3);
+    drop(adder);
+    collect_cycles();
+    assert_eq!(live_scopes(), 1);
+    drop(interpreter);
+    assert_eq!(live_scopes(), 0);
+}
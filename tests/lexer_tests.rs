@@ -3,7 +3,7 @@ use liwb::lexer::*;
 #[test]
 fn empty_source_returns_empty_vector() {
     let source = "";
-    assert_eq!(lexer(source), vec![]);
+    assert!(lexer(source).is_empty());
 }
 
 #[test]
@@ -77,3 +77,165 @@ fn variable_definition() {
         ]
     );
 }
+
+#[test]
+fn tokens_carry_their_position() {
+    let source = "(define x\n  (+ 1 20))";
+    let spans = lexer(source)
+        .into_iter()
+        .map(|lexeme| (lexeme.span.line, lexeme.span.column, lexeme.span.start))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            (1, 1, 0),
+            (1, 2, 1),
+            (1, 9, 8),
+            (2, 3, 12),
+            (2, 4, 13),
+            (2, 6, 15),
+            (2, 8, 17),
+            (2, 10, 19),
+            (2, 11, 20),
+        ]
+    );
+}
+
+#[test]
+fn line_comments_are_skipped() {
+    let source = "; a comment\n(+ 1 2) ; (another one)\n;";
+    assert_eq!(
+        lexer(source),
+        vec![
+            Token::Lparen,
+            Token::Symbol("+".to_string()),
+            Token::Number(1.0),
+            Token::Number(2.0),
+            Token::Rparen,
+        ]
+    );
+    assert_eq!(
+        lexer("(print \"a ; b\")"),
+        vec![
+            Token::Lparen,
+            Token::Symbol("print".to_string()),
+            Token::String("a ; b".to_string()),
+            Token::Rparen,
+        ]
+    );
+}
+
+#[test]
+fn block_comments_can_be_nested() {
+    let source = "#| outer #| inner |# still a comment |#(define x #|1|# 2)";
+    let lexemes = lexer(source);
+    assert_eq!(
+        lexemes,
+        vec![
+            Token::Lparen,
+            Token::Symbol("define".to_string()),
+            Token::Symbol("x".to_string()),
+            Token::Number(2.0),
+            Token::Rparen,
+        ]
+    );
+    assert_eq!(lexemes[0].span.column, 40);
+}
+
+#[test]
+fn unclosed_block_comment_is_an_error() {
+    let lexemes = lexer("(+ 1 2)\n  #| #| |#");
+    assert_eq!(
+        lexemes[5],
+        Token::Error("Unclosed block comment.".to_string())
+    );
+    assert_eq!((lexemes[5].span.line, lexemes[5].span.column), (2, 3));
+}
+
+#[test]
+fn discarding_nothing_is_an_error() {
+    let lexemes = lexer("(foo #_)");
+    assert_eq!(
+        lexemes,
+        vec![
+            Token::Lparen,
+            Token::Symbol("foo".to_string()),
+            Token::Error("Nothing to discard after #_.".to_string()),
+            Token::Rparen,
+        ]
+    );
+    assert_eq!((lexemes[2].span.column, lexemes[2].span.end), (6, 7));
+    assert_eq!(
+        lexer("(foo) #_")[3],
+        Token::Error("Nothing to discard after #_.".to_string())
+    );
+    assert_eq!(
+        lexer("#_ \"unterminated")[0],
+        Token::Error("Unterminated string.".to_string())
+    );
+}
+
+#[test]
+fn shebang_line_is_skipped() {
+    let lexemes = lexer("#!/usr/bin/env liwb\n(exit)");
+    assert_eq!(
+        lexemes,
+        vec![
+            Token::Lparen,
+            Token::Symbol("exit".to_string()),
+            Token::Rparen
+        ]
+    );
+    assert_eq!((lexemes[0].span.line, lexemes[0].span.column), (2, 1));
+    assert_eq!(lexer("(exit) #!")[3], Token::Symbol("#!".to_string()));
+}
+
+#[test]
+fn discarded_forms_are_skipped() {
+    let source = "(+ 1 #_ 2 #_(* 3 [4 (5)]) 6) #_ #_ (a) (b)";
+    assert_eq!(
+        lexer(source),
+        vec![
+            Token::Lparen,
+            Token::Symbol("+".to_string()),
+            Token::Number(1.0),
+            Token::Number(6.0),
+            Token::Rparen,
+        ]
+    );
+}
+
+#[test]
+fn strings_keep_their_spaces_and_escapes() {
+    let source = r#"(print "Hello, World" "tab\there" "\"quoted\" \\ \n" "\u{48}\u{1F600}")"#;
+    assert_eq!(
+        lexer(source),
+        vec![
+            Token::Lparen,
+            Token::Symbol("print".to_string()),
+            Token::String("Hello, World".to_string()),
+            Token::String("tab\there".to_string()),
+            Token::String("\"quoted\" \\ \n".to_string()),
+            Token::String("H\u{1F600}".to_string()),
+            Token::Rparen,
+        ]
+    );
+}
+
+#[test]
+fn bad_strings_are_errors() {
+    let lexemes = lexer("(define name\n  \"John)");
+    assert_eq!(lexemes[3], Token::Error("Unterminated string.".to_string()));
+    assert_eq!((lexemes[3].span.line, lexemes[3].span.column), (2, 3));
+
+    let lexemes = lexer(r#"("a \q")"#);
+    assert_eq!(
+        lexemes[1],
+        Token::Error(r"Invalid escape sequence: \q".to_string())
+    );
+    assert_eq!(lexemes[1].span.column, 5);
+    assert_eq!(
+        lexer(r#""\u{110000}""#)[0],
+        Token::Error(r"Invalid escape sequence: \u{110000}".to_string())
+    );
+}
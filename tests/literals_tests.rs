@@ -0,0 +1,115 @@
+use liwb::error::LiwbError;
+use liwb::literals::*;
+use liwb::Interpreter;
+
+use std::collections::HashMap;
+
+#[test]
+fn plain_values_round_trip() {
+    assert_eq!(f64::from_literal(2.5.into_literal()), Ok(2.5));
+    assert_eq!(i32::from_literal((-7).into_literal()), Ok(-7));
+    assert_eq!(usize::from_literal(Literal::Number(3.0)), Ok(3));
+    assert_eq!(bool::from_literal(true.into_literal()), Ok(true));
+    assert_eq!(
+        String::from_literal("liwb".into_literal()),
+        Ok("liwb".to_string())
+    );
+    assert_eq!(Option::<f64>::from_literal(Literal::Void), Ok(None));
+    assert_eq!(Some(1.0).into_literal(), Literal::Number(1.0));
+    assert_eq!(None::<f64>.into_literal(), Literal::Void);
+}
+
+#[test]
+fn collections_convert_their_elements() {
+    let vector = vec![1, 2, 3].into_literal();
+    assert_eq!(Vec::<u8>::from_literal(vector.clone()), Ok(vec![1, 2, 3]));
+
+    let tuple = (1.5, "two", true).into_literal();
+    assert_eq!(
+        <(f64, String, bool)>::from_literal(tuple),
+        Ok((1.5, "two".to_string(), true))
+    );
+
+    let map = HashMap::from([("a", vec![1]), ("b", vec![])]).into_literal();
+    assert_eq!(
+        HashMap::<String, Vec<i64>>::from_literal(map),
+        Ok(HashMap::from([
+            ("a".to_string(), vec![1]),
+            ("b".to_string(), vec![])
+        ]))
+    );
+}
+
+#[test]
+fn mismatches_are_typed_errors() {
+    assert!(matches!(
+        f64::from_literal("1".into_literal()),
+        Err(LiwbError::TypeMismatch {
+            expected: "number",
+            found: "string",
+            ..
+        })
+    ));
+    assert!(matches!(
+        u8::from_literal(Literal::Number(1.5)),
+        Err(LiwbError::TypeMismatch {
+            expected: "integer",
+            ..
+        })
+    ));
+    assert_eq!(u8::from_literal(Literal::Number(255.0)), Ok(u8::MAX));
+    assert_eq!(i8::from_literal(Literal::Number(-128.0)), Ok(i8::MIN));
+    assert_eq!(
+        i64::from_literal(Literal::Number(-9223372036854775808.0)),
+        Ok(i64::MIN)
+    );
+    assert!(i64::from_literal(Literal::Number(9223372036854775808.0)).is_err());
+    assert!(i64::from_literal(Literal::Number(-9223372036854777856.0)).is_err());
+    assert!(u64::from_literal(Literal::Number(18446744073709551616.0)).is_err());
+    assert!(u64::from_literal(Literal::Number(-1.0)).is_err());
+    assert!(i8::from_literal(Literal::Number(-129.0)).is_err());
+    assert!(i32::from_literal(Literal::Number(f64::NAN)).is_err());
+    assert!(i32::from_literal(Literal::Number(f64::INFINITY)).is_err());
+    assert!(matches!(
+        u8::from_literal(Literal::Number(256.0)),
+        Err(LiwbError::TypeMismatch {
+            expected: "integer",
+            ..
+        })
+    ));
+    assert!(matches!(
+        <(f64, f64)>::from_literal(vec![1.0].into_literal()),
+        Err(LiwbError::TypeMismatch {
+            expected: "vector of 2 elements",
+            found: "vector",
+            ..
+        })
+    ));
+    assert!(matches!(
+        Vec::<bool>::from_literal(vec![1].into_literal()),
+        Err(LiwbError::TypeMismatch {
+            expected: "boolean",
+            found: "number",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn native_functions_can_use_rust_types() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_fn("repeat", 2, |args: &[Literal]| {
+        let text = String::from_literal(args[0].clone())?;
+        let times = usize::from_literal(args[1].clone())?;
+        Ok(vec![text; times].into_literal())
+    });
+    let result = interpreter.eval_str("(repeat \"ab\" (+ 1 1))").unwrap();
+    assert_eq!(
+        Vec::<String>::from_literal(result),
+        Ok(vec!["ab".to_string(), "ab".to_string()])
+    );
+    assert!(matches!(
+        interpreter.eval_str("(repeat \"ab\" 0.5)"),
+        Err(LiwbError::TypeMismatch { .. })
+    ));
+}
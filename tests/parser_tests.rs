@@ -1,6 +1,8 @@
+use liwb::error::LiwbError;
 use liwb::lexer::*;
 use liwb::literals::*;
 use liwb::parser::*;
+use liwb::span::Span;
 
 #[test]
 fn empty_source_returns_void_object() {
@@ -13,11 +15,14 @@ fn basic_arithmetic_operation() {
     let source = "(+ 1 2)";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::MathOperator(MathOperators::Add),
-            Literal::Number(1.0),
-            Literal::Number(2.0),
-        ])
+        Literal::List(
+            vec![
+                Literal::MathOperator(MathOperators::Add),
+                Literal::Number(1.0),
+                Literal::Number(2.0),
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -26,23 +31,35 @@ fn nested() {
     let source = "(+ 1 (+ 2 (+ 3 (+ 4 5))))";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::MathOperator(MathOperators::Add),
-            Literal::Number(1.0),
-            Literal::List(vec![
+        Literal::List(
+            vec![
                 Literal::MathOperator(MathOperators::Add),
-                Literal::Number(2.0),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Add),
-                    Literal::Number(3.0),
-                    Literal::List(vec![
+                Literal::Number(1.0),
+                Literal::List(
+                    vec![
                         Literal::MathOperator(MathOperators::Add),
-                        Literal::Number(4.0),
-                        Literal::Number(5.0),
-                    ])
-                ])
-            ])
-        ])
+                        Literal::Number(2.0),
+                        Literal::List(
+                            vec![
+                                Literal::MathOperator(MathOperators::Add),
+                                Literal::Number(3.0),
+                                Literal::List(
+                                    vec![
+                                        Literal::MathOperator(MathOperators::Add),
+                                        Literal::Number(4.0),
+                                        Literal::Number(5.0),
+                                    ],
+                                    Span::default(),
+                                )
+                            ],
+                            Span::default(),
+                        )
+                    ],
+                    Span::default(),
+                )
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -51,11 +68,14 @@ fn arithmetic_operation_with_void_on_right() {
     let source = "(+ 1 ())";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::MathOperator(MathOperators::Add),
-            Literal::Number(1.0),
-            Literal::Void
-        ])
+        Literal::List(
+            vec![
+                Literal::MathOperator(MathOperators::Add),
+                Literal::Number(1.0),
+                Literal::Void
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -64,31 +84,49 @@ fn arithmetic_operation_with_list_on_both_side() {
     let source = "(+ (+ 1 (* 2 3)) (* (/ 4 5) (- 6 7)))";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::MathOperator(MathOperators::Add),
-            Literal::List(vec![
+        Literal::List(
+            vec![
                 Literal::MathOperator(MathOperators::Add),
-                Literal::Number(1.0),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Multiply),
-                    Literal::Number(2.0),
-                    Literal::Number(3.0),
-                ])
-            ]),
-            Literal::List(vec![
-                Literal::MathOperator(MathOperators::Multiply),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Divide),
-                    Literal::Number(4.0),
-                    Literal::Number(5.0),
-                ]),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Subtract),
-                    Literal::Number(6.0),
-                    Literal::Number(7.0),
-                ]),
-            ]),
-        ])
+                Literal::List(
+                    vec![
+                        Literal::MathOperator(MathOperators::Add),
+                        Literal::Number(1.0),
+                        Literal::List(
+                            vec![
+                                Literal::MathOperator(MathOperators::Multiply),
+                                Literal::Number(2.0),
+                                Literal::Number(3.0),
+                            ],
+                            Span::default(),
+                        )
+                    ],
+                    Span::default(),
+                ),
+                Literal::List(
+                    vec![
+                        Literal::MathOperator(MathOperators::Multiply),
+                        Literal::List(
+                            vec![
+                                Literal::MathOperator(MathOperators::Divide),
+                                Literal::Number(4.0),
+                                Literal::Number(5.0),
+                            ],
+                            Span::default(),
+                        ),
+                        Literal::List(
+                            vec![
+                                Literal::MathOperator(MathOperators::Subtract),
+                                Literal::Number(6.0),
+                                Literal::Number(7.0),
+                            ],
+                            Span::default(),
+                        ),
+                    ],
+                    Span::default(),
+                ),
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -97,15 +135,21 @@ fn define_variable() {
     let source = "(define pi (/ 22 7))";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::Symbol("define".to_string()),
-            Literal::Symbol("pi".to_string()),
-            Literal::List(vec![
-                Literal::MathOperator(MathOperators::Divide),
-                Literal::Number(22.0),
-                Literal::Number(7.0),
-            ])
-        ]),
+        Literal::List(
+            vec![
+                Literal::Symbol("define".to_string()),
+                Literal::Symbol("pi".to_string()),
+                Literal::List(
+                    vec![
+                        Literal::MathOperator(MathOperators::Divide),
+                        Literal::Number(22.0),
+                        Literal::Number(7.0),
+                    ],
+                    Span::default(),
+                )
+            ],
+            Span::default(),
+        ),
     )
 }
 
@@ -115,33 +159,51 @@ fn multi_line_statement() {
     assert_eq!(
         parser(lexer(source)).unwrap(),
         vec![
-            Literal::List(vec![
-                Literal::Symbol("define".to_string()),
-                Literal::Symbol("pi".to_string()),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Divide),
-                    Literal::Number(22.0),
-                    Literal::Number(7.0),
-                ])
-            ]),
-            Literal::List(vec![
-                Literal::Symbol("define".to_string()),
-                Literal::Symbol("r".to_string()),
-                Literal::Number(10.0)
-            ]),
-            Literal::List(vec![
-                Literal::Symbol("define".to_string()),
-                Literal::Symbol("area-of-circle".to_string()),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Multiply),
+            Literal::List(
+                vec![
+                    Literal::Symbol("define".to_string()),
                     Literal::Symbol("pi".to_string()),
-                    Literal::List(vec![
-                        Literal::MathOperator(MathOperators::Multiply),
-                        Literal::Symbol("r".to_string()),
-                        Literal::Symbol("r".to_string()),
-                    ])
-                ])
-            ]),
+                    Literal::List(
+                        vec![
+                            Literal::MathOperator(MathOperators::Divide),
+                            Literal::Number(22.0),
+                            Literal::Number(7.0),
+                        ],
+                        Span::default(),
+                    )
+                ],
+                Span::default(),
+            ),
+            Literal::List(
+                vec![
+                    Literal::Symbol("define".to_string()),
+                    Literal::Symbol("r".to_string()),
+                    Literal::Number(10.0)
+                ],
+                Span::default(),
+            ),
+            Literal::List(
+                vec![
+                    Literal::Symbol("define".to_string()),
+                    Literal::Symbol("area-of-circle".to_string()),
+                    Literal::List(
+                        vec![
+                            Literal::MathOperator(MathOperators::Multiply),
+                            Literal::Symbol("pi".to_string()),
+                            Literal::List(
+                                vec![
+                                    Literal::MathOperator(MathOperators::Multiply),
+                                    Literal::Symbol("r".to_string()),
+                                    Literal::Symbol("r".to_string()),
+                                ],
+                                Span::default(),
+                            )
+                        ],
+                        Span::default(),
+                    )
+                ],
+                Span::default(),
+            ),
         ]
     );
 }
@@ -151,11 +213,14 @@ fn boolean_variable() {
     let source = "(define x false)";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::Symbol("define".to_string()),
-            Literal::Symbol("x".to_string()),
-            Literal::Boolean(false)
-        ])
+        Literal::List(
+            vec![
+                Literal::Symbol("define".to_string()),
+                Literal::Symbol("x".to_string()),
+                Literal::Boolean(false)
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -164,11 +229,14 @@ fn define_string_variable() {
     let source = "(define message \"Hello, World\")";
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::Symbol("define".to_string()),
-            Literal::Symbol("message".to_string()),
-            Literal::String("Hello, World".to_string()),
-        ])
+        Literal::List(
+            vec![
+                Literal::Symbol("define".to_string()),
+                Literal::Symbol("message".to_string()),
+                Literal::String("Hello, World".to_string()),
+            ],
+            Span::default(),
+        )
     );
 }
 
@@ -177,20 +245,136 @@ fn vector_definition() {
     let source = r#"(define numbers [1 "two" 3 "four" (+ 4 1)])"#;
     assert_eq!(
         parser(lexer(source)).unwrap()[0],
-        Literal::List(vec![
-            Literal::Symbol("define".to_string()),
-            Literal::Symbol("numbers".to_string()),
-            Literal::Vector(vec![
-                Literal::Number(1.0),
-                Literal::String("two".to_string()),
-                Literal::Number(3.0),
-                Literal::String("four".to_string()),
-                Literal::List(vec![
-                    Literal::MathOperator(MathOperators::Add),
-                    Literal::Number(4.0),
-                    Literal::Number(1.0)
-                ])
-            ]),
-        ])
+        Literal::List(
+            vec![
+                Literal::Symbol("define".to_string()),
+                Literal::Symbol("numbers".to_string()),
+                Literal::Vector(vec![
+                    Literal::Number(1.0),
+                    Literal::String("two".to_string()),
+                    Literal::Number(3.0),
+                    Literal::String("four".to_string()),
+                    Literal::List(
+                        vec![
+                            Literal::MathOperator(MathOperators::Add),
+                            Literal::Number(4.0),
+                            Literal::Number(1.0)
+                        ],
+                        Span::default(),
+                    )
+                ]),
+            ],
+            Span::default(),
+        )
+    );
+}
+
+#[test]
+fn unclosed_parenthesis_points_to_the_opening_one() {
+    let source = "(define x 10)\n(print (+ x 1)";
+    let err = parser(lexer(source)).unwrap_err();
+    assert_eq!((err.span().line, err.span().column), (2, 1));
+    assert_eq!(
+        err.render("main.liwb", source),
+        "main.liwb:2:1: Unclosed parenthesis.\n  |\n2 | (print (+ x 1)\n  | ^"
+    );
+}
+
+#[test]
+fn lists_carry_their_span() {
+    let source = "(print\n  (+ 1 2))";
+    let Literal::List(list, span) = &parser(lexer(source)).unwrap()[0] else {
+        panic!("Expected a list");
+    };
+    assert_eq!(
+        (span.line, span.column, span.start, span.end),
+        (1, 1, 0, 17)
+    );
+    let Literal::List(_, span) = &list[1] else {
+        panic!("Expected a list");
+    };
+    assert_eq!(
+        (span.line, span.column, span.start, span.end),
+        (2, 3, 9, 16)
+    );
+}
+
+#[test]
+fn lists_are_compared_without_their_span() {
+    let literals = parser(lexer("(+ 1 2)\n  (+ 1 2)")).unwrap();
+    let (Literal::List(_, first), Literal::List(_, second)) = (&literals[0], &literals[1]) else {
+        panic!("Expected two lists");
+    };
+    assert_ne!(first, second);
+    assert_eq!(literals[0], literals[1]);
+    assert!(literals[0] < parser(lexer("(+ 1 3)")).unwrap()[0]);
+}
+
+#[test]
+fn parse_errors_have_their_own_kind() {
+    assert!(matches!(
+        parser(lexer("(+ 1 2")),
+        Err(LiwbError::Parse { .. })
+    ));
+    assert!(matches!(parser(lexer(")")), Err(LiwbError::Parse { .. })));
+}
+
+#[test]
+fn mismatched_brackets_point_to_the_wrong_closer() {
+    let err = parser(lexer("(+ 1 2]")).unwrap_err();
+    assert_eq!(
+        err.message(),
+        "Expected `)` to close the `(` at 1:1, found `]`."
+    );
+    assert_eq!((err.span().line, err.span().column), (1, 7));
+    let err = parser(lexer("(print\n  {1 2))")).unwrap_err();
+    assert_eq!(
+        err.message(),
+        "Expected `}` to close the `{` at 2:3, found `)`."
+    );
+    assert_eq!((err.span().line, err.span().column), (2, 7));
+    assert!(parser(lexer("(print [1 (+ 1 2)] {\"a\" [3]})")).is_ok());
+}
+
+#[test]
+fn parse_errors_name_the_tokens_plainly() {
+    let message = |source: &str| parser(lexer(source)).unwrap_err().message();
+    assert_eq!(message(")"), "Unexpected `)`.");
+    assert_eq!(message("x"), "Expected `(`, found symbol `x`.");
+    assert_eq!(message("(print 1) 2"), "Expected `(`, found number `2`.");
+    assert_eq!(message("\"a\""), "Expected `(`, found string \"a\".");
+}
+
+#[test]
+fn unclosed_block_comment_is_a_lex_error() {
+    let error = parser(lexer("(+ 1 2) #| never closed")).unwrap_err();
+    assert!(matches!(error, LiwbError::Lex { .. }));
+    assert_eq!(error.span().column, 9);
+}
+
+#[test]
+fn maps_are_hash_map_calls() {
+    assert_eq!(
+        parser(lexer("(define m {\"a\" 1 \"b\" {}})")).unwrap(),
+        vec![Literal::List(
+            vec![
+                Literal::Symbol("define".to_string()),
+                Literal::Symbol("m".to_string()),
+                Literal::List(
+                    vec![
+                        Literal::Symbol("hash-map".to_string()),
+                        Literal::String("a".to_string()),
+                        Literal::Number(1.0),
+                        Literal::String("b".to_string()),
+                        Literal::List(
+                            vec![Literal::Symbol("hash-map".to_string())],
+                            Span::default()
+                        ),
+                    ],
+                    Span::default()
+                ),
+            ],
+            Span::default()
+        )]
     );
 }
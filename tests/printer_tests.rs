@@ -0,0 +1,57 @@
+use liwb::lexer::lexer;
+use liwb::literals::*;
+use liwb::parser::parser;
+use liwb::printer::*;
+use liwb::Interpreter;
+
+fn eval(source: &str) -> Literal {
+    Interpreter::new().eval_str(source).unwrap()
+}
+
+#[test]
+fn values_are_written_in_liwb_syntax() {
+    assert_eq!(to_source(&eval("(+ 1 2)")), "3");
+    assert_eq!(to_source(&eval("(/ 1 4)")), "0.25");
+    assert_eq!(to_source(&eval("(range 1 3)")), "[1 2 3]");
+    assert_eq!(
+        to_source(&eval("(do \"a \\\"b\\\"\\n\")")),
+        r#""a \"b\"\n""#
+    );
+    assert_eq!(
+        to_source(&eval("(hash-map \"a\" 1 2 true)")),
+        r#"{2 true "a" 1}"#
+    );
+    assert_eq!(to_source(&eval("(fn fib [n] n) (do fib)")), "#<fn fib [n]>");
+    assert_eq!(to_source(&eval("(define x 1)")), "()");
+}
+
+#[test]
+fn written_values_are_read_back() {
+    let value = eval("(hash-map \"s\" \"tab\\there\" \"v\" (range 1 3) \"m\" {1 false})");
+    let source = to_source(&value);
+    assert_eq!(eval(&format!("(do {source})")), value);
+
+    let source = "(+ 1 (nth [2 3] 0))";
+    let literals = parser(lexer(source)).unwrap();
+    assert_eq!(to_source(&literals[0]), source);
+}
+
+#[test]
+fn values_that_do_not_fit_are_broken_over_lines() {
+    let value = eval("(range 1 3)");
+    assert_eq!(pretty(&value, 80), "[1 2 3]");
+    assert_eq!(pretty(&value, 5), "[\n  1 2\n  3\n]");
+    assert_eq!(pretty(&value, 4), "[\n  1\n  2\n  3\n]");
+
+    let value = eval("(hash-map \"a\" (range 1 3) \"b\" 2)");
+    assert_eq!(pretty(&value, 13), "{\n  \"a\" [1 2 3]\n  \"b\" 2\n}");
+    assert_eq!(
+        pretty(&value, 10),
+        "{\n  \"a\" [\n    1 2 3\n  ]\n  \"b\" 2\n}"
+    );
+
+    let value = eval("(hash-map \"k\" (range 0 100))");
+    let printed = pretty(&value, DEFAULT_WIDTH);
+    assert!(printed.lines().all(|line| line.len() <= DEFAULT_WIDTH));
+    assert_eq!(eval(&format!("(do {printed})")), value);
+}
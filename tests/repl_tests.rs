@@ -0,0 +1,242 @@
+use liwb::lexer::lexer;
+use liwb::literals::Literal;
+use liwb::parser::parser;
+use liwb::repl::{completions, format_results, is_incomplete, run_command};
+use liwb::session::Session;
+use liwb::Interpreter;
+
+#[test]
+fn unbalanced_forms_are_incomplete() {
+    assert!(is_incomplete("(fn square [x]\n"));
+    assert!(is_incomplete("(fn square [x]\n  (* x x)"));
+    assert!(is_incomplete("(define v [1 2\n"));
+    assert!(is_incomplete("(define m {\"a\" 1\n"));
+    assert!(!is_incomplete("(fn square [x]\n  (* x x))\n"));
+    assert!(!is_incomplete("(print 1) (print 2)\n(print 3)\n"));
+}
+
+#[test]
+fn brackets_in_strings_and_comments_are_ignored() {
+    assert!(!is_incomplete("(print \"(\") ; (\n"));
+    assert!(!is_incomplete("#| ( |# (print 1)\n"));
+    assert!(is_incomplete("(print \"multi\nline"));
+    assert!(is_incomplete("#| a comment\n"));
+}
+
+#[test]
+fn discarding_at_the_end_of_the_input_is_incomplete() {
+    assert!(is_incomplete("(print 1) #_\n"));
+    assert!(!is_incomplete("(print #_)\n"));
+}
+
+#[test]
+fn errors_are_left_to_the_parser() {
+    assert!(!is_incomplete("(print 1))\n"));
+    assert!(!is_incomplete(") (\n"));
+    assert!(!is_incomplete("(print \"\\q\" (\n"));
+}
+
+#[test]
+fn builtins_and_globals_are_completed() {
+    let globals = vec!["square".to_string(), "sum".to_string()];
+    assert_eq!(
+        completions("(s", 2, &globals),
+        (
+            1,
+            vec![
+                "sin".to_string(),
+                "sqrt".to_string(),
+                "square".to_string(),
+                "str".to_string(),
+                "sum".to_string()
+            ]
+        )
+    );
+    assert_eq!(
+        completions("(map squ [1 2])", 8, &globals),
+        (5, vec!["square".to_string()])
+    );
+    assert_eq!(completions("(print ", 7, &globals), (7, Vec::new()));
+}
+
+#[test]
+fn commands_inspect_the_session() {
+    let mut session = Session::new();
+    session
+        .eval("(fn square [x] (* x x)) (define two 2)")
+        .unwrap();
+    session
+        .interpreter
+        .register_fn("shout", 1, |args| Ok(args[0].clone()));
+
+    assert_eq!(
+        run_command("env", &mut session),
+        Ok("shout: native function of 1 argument\nsquare: function [x]\ntwo: number 2".to_string())
+    );
+    assert_eq!(
+        run_command("type (square two)", &mut session),
+        Ok("number".to_string())
+    );
+    assert_eq!(
+        run_command("type (do square)", &mut session),
+        Ok("function".to_string())
+    );
+    assert_eq!(
+        run_command("type (cube 2)", &mut session),
+        Err("<repl>:1:1: Unknow symbol: cube\n  |\n1 | (cube 2)\n  | ^^^^^^^^".to_string())
+    );
+    let timed = run_command("time (square 3)", &mut session).unwrap();
+    assert!(timed.starts_with("9\nEvaluated in "));
+    assert!(run_command("ast (+ 1 2)", &mut session)
+        .unwrap()
+        .starts_with("List([MathOperator(Add), Number(1.0), Number(2.0)]"));
+
+    assert_eq!(run_command("reset", &mut session), Ok(String::new()));
+    assert_eq!(run_command("env", &mut session), Ok(String::new()));
+}
+
+#[test]
+fn files_are_loaded_into_the_session() {
+    let mut session = Session::new();
+    assert_eq!(
+        run_command("load liwb/let.liwb", &mut session),
+        Ok(String::new())
+    );
+    assert!(!session.interpreter.global_names().is_empty());
+    assert!(run_command("load", &mut session)
+        .unwrap_err()
+        .starts_with("Usage: :load"));
+    assert!(run_command("load missing.liwb", &mut session).is_err());
+    assert_eq!(
+        run_command("nope", &mut session),
+        Err("Unknown command :nope, try :help.".to_string())
+    );
+}
+
+#[test]
+fn results_are_printed_without_definitions() {
+    let results = Interpreter::new()
+        .eval_literals(parser(lexer("(fn square [x] (* x x)) (square 3) (range 1 3)")).unwrap())
+        .unwrap();
+    assert_eq!(format_results(&results), "9\n[1 2 3]");
+}
+
+#[test]
+fn sessions_are_saved_as_scripts() {
+    let mut session = Session::new();
+    session
+        .eval("(define two 2) (fn square [x]\n  (* x x)) (print (square two))")
+        .unwrap();
+    session.eval("(define two 3) (square two)").unwrap();
+    assert!(session.eval("(define broken (cube 2))").is_err());
+    assert_eq!(
+        session.script(),
+        "(define two 2)\n(fn square [x]\n  (* x x))\n(define two 3)\n"
+    );
+
+    let path = std::env::temp_dir().join("liwb_saved_session.liwb");
+    let command = format!("save {}", path.display());
+    assert_eq!(
+        run_command(&command, &mut session),
+        Ok(format!("Saved the session to {}.", path.display()))
+    );
+
+    let mut restored = Session::new();
+    let command = format!("restore {}", path.display());
+    assert_eq!(run_command(&command, &mut restored), Ok(String::new()));
+    assert_eq!(
+        restored.interpreter.eval_str("(square two)"),
+        Ok(Literal::Number(9.0))
+    );
+    assert_eq!(restored.script(), session.script());
+
+    assert_eq!(run_command("reset", &mut restored), Ok(String::new()));
+    assert_eq!(restored.script(), "");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn redefinitions_are_restored_in_order() {
+    let mut session = Session::new();
+    session
+        .eval("(define x 1) (define x (+ x 1)) (define a 1) (define b (+ a 1)) (define a 5)")
+        .unwrap();
+    let path = std::env::temp_dir().join("liwb_redefined_session.liwb");
+    session.save(&path).unwrap();
+
+    let mut restored = Session::new();
+    let command = format!("restore {}", path.display());
+    assert_eq!(run_command(&command, &mut restored), Ok(String::new()));
+    for (name, value) in [("x", 2.0), ("a", 5.0), ("b", 2.0)] {
+        assert_eq!(
+            restored.interpreter.get_global(name),
+            Some(Literal::Number(value))
+        );
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn errors_are_rendered_against_the_source_they_come_from() {
+    let mut session = Session::new();
+    session.eval("(fn f [x] (+ x \"a\"))").unwrap();
+    session
+        .eval_named("lib.liwb", "\n(fn g [x]\n  (+ x \"b\"))")
+        .unwrap();
+
+    let err = session.eval("(f 1)").unwrap_err();
+    assert_eq!(
+        session.render(&err),
+        "<repl>:1:11: Expected number, found string.\n  |\n1 | (fn f [x] (+ x \"a\"))\n  |           ^^^^^^^^^"
+    );
+    let err = session.eval("(g \"ñññññññññññññ\")").unwrap_err();
+    assert!(session.render(&err).starts_with("lib.liwb:3:3: "));
+
+    // Rendering against another source can't point into it, but doesn't panic.
+    assert_eq!(
+        err.render("<repl>", "(g \"ñññññññññññññ\")"),
+        "<repl>:3:3: Expected number, found string."
+    );
+    let err = session.eval("(f \"ñññññññññññ\")").unwrap_err();
+    assert_eq!(
+        err.render("<repl>", "(f \"ñññññññññññ\")"),
+        "<repl>:1:11: Expected number, found string."
+    );
+}
+
+#[test]
+fn every_applied_definition_is_saved() {
+    let lib = std::env::temp_dir().join("liwb_session_lib.liwb");
+    std::fs::write(&lib, "(define lib 1)").unwrap();
+    let mut session = Session::new();
+    let command = format!("load {}", lib.display());
+    assert_eq!(run_command(&command, &mut session), Ok(String::new()));
+    session.eval("(define c (+ lib 1))").unwrap();
+    assert!(run_command("time (define t 10)", &mut session).is_ok());
+    assert!(run_command("type (define ty 20)", &mut session).is_ok());
+    // The definition before the failing form was applied, so it is kept.
+    assert!(session.eval("(define a 1) (oops)").is_err());
+    session.eval("(define b (+ a 1))").unwrap();
+
+    let path = std::env::temp_dir().join("liwb_applied_session.liwb");
+    let command = format!("save {}", path.display());
+    assert!(run_command(&command, &mut session).is_ok());
+    let mut restored = Session::new();
+    let command = format!("restore {}", path.display());
+    assert_eq!(run_command(&command, &mut restored), Ok(String::new()));
+    for (name, value) in [
+        ("lib", 1.0),
+        ("c", 2.0),
+        ("t", 10.0),
+        ("ty", 20.0),
+        ("a", 1.0),
+        ("b", 2.0),
+    ] {
+        assert_eq!(
+            restored.interpreter.get_global(name),
+            Some(Literal::Number(value))
+        );
+    }
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(lib).unwrap();
+}
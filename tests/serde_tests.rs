@@ -0,0 +1,52 @@
+#![cfg(feature = "serde")]
+
+use liwb::literals::*;
+use liwb::Interpreter;
+
+#[test]
+fn values_are_serialized_as_json() {
+    let mut interpreter = Interpreter::new();
+    let result = interpreter
+        .eval_str("{\"list\" [1 2.5 \"three\" true []] \"map\" {1 false} \"void\" ()}")
+        .unwrap();
+    assert_eq!(
+        serde_json::to_string(&result).unwrap(),
+        r#"{"list":[1.0,2.5,"three",true,[]],"map":{"1.0":false},"void":null}"#
+    );
+}
+
+#[test]
+fn json_is_deserialized_into_literals() {
+    let literal: Literal = serde_json::from_str(
+        r#"{"numbers": [1, -2, 3.5], "name": "liwb", "ok": true, "none": null}"#,
+    )
+    .unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("data", literal);
+    assert_eq!(
+        interpreter.eval_str("(nth (get data \"numbers\") 1)"),
+        Ok(Literal::Number(-2.0))
+    );
+    assert_eq!(
+        interpreter.eval_str("(get data \"none\" 0)"),
+        Ok(Literal::Void)
+    );
+    assert_eq!(
+        interpreter.eval_str("(keys data)"),
+        Ok(vec!["name", "none", "numbers", "ok"].into_literal())
+    );
+}
+
+#[test]
+fn functions_can_not_be_serialized() {
+    let mut interpreter = Interpreter::new();
+    let function = interpreter.eval_str("(lambda [x] x)").unwrap();
+    let error = serde_json::to_string(&function).unwrap_err();
+    assert!(error.to_string().contains("Can not serialize a function"));
+
+    let unevaluated = interpreter
+        .eval_str("(define v [1 (+ 1 1)]) (get {1 v} 1)")
+        .unwrap();
+    let error = serde_json::to_string(&unevaluated).unwrap_err();
+    assert!(error.to_string().contains("Can not serialize a list"));
+}